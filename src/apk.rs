@@ -0,0 +1,159 @@
+use crate::backend::{self, PackageBackend, ScanScope};
+use crate::types::{ConfigLeftover, Package, PackageSystem, OS};
+use std::collections::HashSet;
+use std::path::Path;
+
+const WORLD_PATH: &str = "etc/apk/world";
+const INSTALLED_DB_PATH: &str = "lib/apk/db/installed";
+
+pub struct Apk;
+
+impl PackageBackend for Apk {
+    fn name(&self) -> &'static str {
+        "apk"
+    }
+
+    fn os(&self) -> OS {
+        OS::Alpine
+    }
+
+    fn system(&self) -> PackageSystem {
+        PackageSystem::Apk
+    }
+
+    fn detect(&self, root: &Path) -> bool {
+        root.join("sbin/apk").exists()
+    }
+
+    fn list_candidates(&self, scope: &ScanScope) -> anyhow::Result<Vec<Package>> {
+        if scope.orphans {
+            list_orphans(&scope.root)
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    fn installed_packages(&self, scope: &ScanScope) -> anyhow::Result<Vec<String>> {
+        let installed = std::fs::read_to_string(scope.root.join(INSTALLED_DB_PATH))?;
+        Ok(parse_installed_db(&installed).into_iter().map(|p| p.name).collect())
+    }
+
+    fn list_leftovers(&self, scope: &ScanScope) -> anyhow::Result<Vec<ConfigLeftover>> {
+        Ok(backend::find_config_leftovers(&scope.root, &[(".apk-new", backend::UNMERGED_CONFIG)]))
+    }
+
+    fn removal_command(&self, pkgs: &[&Package]) -> Vec<String> {
+        let mut cmd = vec!["apk".to_string(), "del".to_string()];
+        cmd.extend(pkgs.iter().map(|p| p.name.clone()));
+        cmd
+    }
+}
+
+struct InstalledPackage {
+    name: String,
+    version: String,
+    description: String,
+    depends: Vec<String>,
+    provides: Vec<String>,
+    install_if: Vec<String>,
+}
+
+/// Installed packages that are neither in the world file, required by any
+/// other installed package, nor pulled in by a satisfied `install_if`.
+pub fn list_orphans(root: &Path) -> anyhow::Result<Vec<Package>> {
+    let world = std::fs::read_to_string(root.join(WORLD_PATH))?;
+    let installed = std::fs::read_to_string(root.join(INSTALLED_DB_PATH))?;
+
+    let world: HashSet<&str> = world.split_whitespace().map(strip_constraint).collect();
+    let installed = parse_installed_db(&installed);
+
+    let required: HashSet<&str> = installed
+        .iter()
+        .flat_map(|p| p.depends.iter())
+        .filter(|d| !d.starts_with('!'))
+        .map(|d| strip_constraint(d))
+        .collect();
+
+    let present: HashSet<&str> = installed
+        .iter()
+        .flat_map(|p| std::iter::once(p.name.as_str()).chain(p.provides.iter().map(|prov| strip_constraint(prov))))
+        .collect();
+
+    // apk would install these again as soon as they were removed, e.g. `foo-doc` next to `foo` and `docs`
+    let auto_installed = |p: &InstalledPackage| {
+        !p.install_if.is_empty()
+            && p.install_if.iter().all(|cond| match cond.strip_prefix('!') {
+                Some(excluded) => !present.contains(strip_constraint(excluded)),
+                None => present.contains(strip_constraint(cond)),
+            })
+    };
+
+    let packages = installed
+        .iter()
+        .filter(|p| !world.contains(p.name.as_str()))
+        .filter(|p| !auto_installed(p))
+        .filter(|p| {
+            !required.contains(p.name.as_str())
+                && !p.provides.iter().any(|prov| required.contains(strip_constraint(prov)))
+        })
+        .map(|p| Package {
+            name: p.name.clone(),
+            version: p.version.clone(),
+            description: p.description.clone(),
+            installed: true,
+            installed_size: None,
+            system: PackageSystem::Apk,
+            explanation: None,
+            protected: false,
+            category: None,
+        })
+        .collect();
+
+    Ok(packages)
+}
+
+/// Parses the `/lib/apk/db/installed` format: blank-line separated
+/// records of `X:value` lines.
+fn parse_installed_db(contents: &str) -> Vec<InstalledPackage> {
+    let mut packages = Vec::new();
+
+    for record in contents.split("\n\n") {
+        let mut pkg = InstalledPackage {
+            name: String::new(),
+            version: String::new(),
+            description: String::new(),
+            depends: Vec::new(),
+            provides: Vec::new(),
+            install_if: Vec::new(),
+        };
+
+        for line in record.lines() {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+
+            match key {
+                "P" => pkg.name = value.to_string(),
+                "V" => pkg.version = value.to_string(),
+                "T" => pkg.description = value.to_string(),
+                "D" => pkg.depends = value.split_whitespace().map(str::to_string).collect(),
+                "p" => pkg.provides = value.split_whitespace().map(str::to_string).collect(),
+                "i" => pkg.install_if = value.split_whitespace().map(str::to_string).collect(),
+                _ => {},
+            }
+        }
+
+        if !pkg.name.is_empty() {
+            packages.push(pkg);
+        }
+    }
+
+    packages
+}
+
+/// Reduces a world or dependency entry such as `foo>=1.2`, `foo@edge`
+/// or `so:libc.musl-x86_64.so.1=1` to the bare name.
+fn strip_constraint(dep: &str) -> &str {
+    let end = dep.find(['<', '>', '=', '~', '@']).unwrap_or(dep.len());
+    &dep[..end]
+}
@@ -0,0 +1,367 @@
+use crate::config::{matches_glob, Config};
+use crate::depgraph::DependencyGraph;
+use crate::types::{compare_versions, format_size, CacheFile, ConfigLeftover, Kernel, Package, PackageSystem, SweepItem, OS};
+use crate::{apk, dpkg, flatpak, home_scanner, nix, pacman, portage, rpm, snap, xbps, zypper};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Which kinds of candidates a scan should report, and for which system.
+#[derive(Debug, Clone)]
+pub struct ScanScope {
+    pub orphans: bool,
+    pub residual: bool,
+    /// Root of the inspected system; `/` unless `--root` is given.
+    pub root: PathBuf,
+    /// How many of the newest kernels are never offered for removal.
+    pub keep_kernels: usize,
+    /// How many older cached versions of each package are kept besides the installed one.
+    pub keep_cached: usize,
+    /// `protected_packages` patterns from the config.
+    pub protected: Vec<String>,
+}
+
+impl ScanScope {
+    /// Whether the running system itself is inspected, so its tools and
+    /// running kernel describe `root`.
+    pub fn is_host(&self) -> bool {
+        self.root == Path::new("/")
+    }
+}
+
+/// Anything sweep can collect items from, native package manager or not.
+pub trait Scanner {
+    /// Key used for this scanner in the `[backends]` config table.
+    fn name(&self) -> &'static str;
+
+    /// Whether this scanner's software is installed under `root`.
+    fn detect(&self, root: &Path) -> bool;
+
+    /// The OS this scanner is the native package manager of, if any.
+    fn native_os(&self) -> Option<OS> {
+        None
+    }
+
+    /// Whether the scanner can inspect a `--root` other than the running host.
+    fn supports_root(&self) -> bool {
+        true
+    }
+
+    fn scan(&self, scope: &ScanScope) -> anyhow::Result<Vec<SweepItem>>;
+
+    /// Lowercased names of everything installed through this scanner, used
+    /// by the deep scan to tell which home entries still have an owner.
+    fn installed_names(&self, _scope: &ScanScope) -> anyhow::Result<HashSet<String>> {
+        Ok(HashSet::new())
+    }
+}
+
+/// A package manager sweep knows how to query and clean up after.
+pub trait PackageBackend {
+    fn name(&self) -> &'static str;
+
+    fn os(&self) -> OS;
+
+    fn system(&self) -> PackageSystem;
+
+    /// Whether this package manager is installed under `root`.
+    fn detect(&self, root: &Path) -> bool;
+
+    /// Whether `list_candidates` reads the database under `scope.root`
+    /// rather than asking the running system's package manager.
+    fn supports_root(&self) -> bool {
+        true
+    }
+
+    fn list_candidates(&self, scope: &ScanScope) -> anyhow::Result<Vec<Package>>;
+
+    /// Dependency graph of the installed packages, used to explain why a
+    /// candidate was flagged and what its removal would orphan in turn.
+    fn dependency_graph(&self, _scope: &ScanScope) -> anyhow::Result<Option<DependencyGraph>> {
+        Ok(None)
+    }
+
+    /// Packages the system cannot do without, e.g. dpkg's `Essential: yes`.
+    fn essential_packages(&self, _scope: &ScanScope) -> anyhow::Result<HashSet<String>> {
+        Ok(HashSet::new())
+    }
+
+    /// Names of every installed package.
+    fn installed_packages(&self, _scope: &ScanScope) -> anyhow::Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    /// Paths the package installed, used to tell which home directories
+    /// belong to it. Empty if the backend cannot list them.
+    fn package_files(&self, _root: &Path, _name: &str) -> anyhow::Result<Vec<PathBuf>> {
+        Ok(Vec::new())
+    }
+
+    /// Every installed package with its `package_files`, for backends that
+    /// can read them all more cheaply than one package at a time.
+    fn installed_files(&self, scope: &ScanScope) -> anyhow::Result<Vec<(String, Vec<PathBuf>)>> {
+        let packages = self.installed_packages(scope)?.into_iter().map(|pkg| {
+            let files = self.package_files(&scope.root, &pkg).unwrap_or_default();
+            (pkg, files)
+        });
+
+        Ok(packages.collect())
+    }
+
+    /// Stray config files left behind by upgrades or removals.
+    fn list_leftovers(&self, _scope: &ScanScope) -> anyhow::Result<Vec<ConfigLeftover>> {
+        Ok(Vec::new())
+    }
+
+    /// Installed kernels that are neither running nor among the newest `scope.keep_kernels`.
+    fn list_kernels(&self, _scope: &ScanScope) -> anyhow::Result<Vec<Kernel>> {
+        Ok(Vec::new())
+    }
+
+    /// Command (without `su_command`) that removes an old kernel's packages.
+    /// Empty if the backend tracks kernels by their files only.
+    fn kernel_removal_command(&self, _kernel: &Kernel) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Cached downloads the package manager no longer needs.
+    fn list_caches(&self, _scope: &ScanScope) -> anyhow::Result<Vec<CacheFile>> {
+        Ok(Vec::new())
+    }
+
+    /// Command (without `su_command`) that removes all of `pkgs` in a single transaction.
+    fn removal_command(&self, pkgs: &[&Package]) -> Vec<String>;
+
+    /// Asks the package manager what removing `pkgs` would actually take
+    /// out, without changing anything. `None` if it has no way to simulate.
+    fn simulate_removal(&self, _pkgs: &[&Package]) -> anyhow::Result<Option<Vec<String>>> {
+        Ok(None)
+    }
+
+    fn describe(&self, pkg: &Package) -> String {
+        if !pkg.installed {
+            return pkg.description.clone();
+        }
+
+        match pkg.installed_size {
+            Some(size) => format!("{} {} [{}]", pkg.version, pkg.description, format_size(size)),
+            None => format!("{} {}", pkg.version, pkg.description),
+        }
+    }
+
+    fn dry_run_plan(&self, pkgs: &[&Package]) -> String {
+        self.removal_command(pkgs).join(" ")
+    }
+
+    fn remove(&self, pkgs: &[&Package], su_command: &str) -> anyhow::Result<()> {
+        let status = Command::new(su_command)
+            .args(self.removal_command(pkgs))
+            .status()?;
+
+        if !status.success() {
+            let names: Vec<&str> = pkgs.iter().map(|p| p.name.as_str()).collect();
+            anyhow::bail!("Failed to remove package: {}", names.join(", "));
+        }
+
+        Ok(())
+    }
+}
+
+// On the boxed backends `all()` returns, so they register as scanners from that one list
+impl<T: PackageBackend + ?Sized> Scanner for Box<T> {
+    fn name(&self) -> &'static str {
+        PackageBackend::name(self.as_ref())
+    }
+
+    fn detect(&self, root: &Path) -> bool {
+        PackageBackend::detect(self.as_ref(), root)
+    }
+
+    fn supports_root(&self) -> bool {
+        PackageBackend::supports_root(self.as_ref())
+    }
+
+    fn native_os(&self) -> Option<OS> {
+        Some(self.os())
+    }
+
+    fn scan(&self, scope: &ScanScope) -> anyhow::Result<Vec<SweepItem>> {
+        let mut packages = self.list_candidates(scope)?;
+
+        let essential = self.essential_packages(scope)?;
+        for pkg in &mut packages {
+            pkg.protected = essential.contains(&pkg.name)
+                || scope.protected.iter().any(|pattern| matches_glob(pattern, &pkg.name));
+        }
+
+        if packages.iter().any(|p| p.installed)
+            && let Some(graph) = self.dependency_graph(scope)?
+        {
+            for pkg in &mut packages {
+                pkg.explanation = graph.explain(&pkg.name);
+            }
+        }
+
+        let mut items: Vec<SweepItem> = packages.into_iter().map(SweepItem::Package).collect();
+
+        if scope.orphans {
+            items.extend(self.list_kernels(scope)?.into_iter().map(SweepItem::Kernel));
+        }
+
+        if scope.residual {
+            items.extend(self.list_leftovers(scope)?.into_iter().map(SweepItem::ConfigLeftover));
+            items.extend(self.list_caches(scope)?.into_iter().map(SweepItem::CacheFile));
+        }
+
+        Ok(items)
+    }
+
+    /// Package names plus the names derived from their file lists.
+    fn installed_names(&self, scope: &ScanScope) -> anyhow::Result<HashSet<String>> {
+        let mut names = HashSet::new();
+
+        for (pkg, files) in self.installed_files(scope)? {
+            names.extend(home_scanner::expected_names(&pkg, &files).into_iter().map(|(name, _)| name));
+        }
+
+        Ok(names)
+    }
+}
+
+/// Every package backend sweep knows about, in detection order.
+pub fn all() -> Vec<Box<dyn PackageBackend>> {
+    vec![
+        Box::new(xbps::Xbps),
+        Box::new(dpkg::Dpkg),
+        Box::new(pacman::Pacman),
+        Box::new(rpm::Rpm),
+        Box::new(apk::Apk),
+        Box::new(zypper::Zypper),
+        Box::new(portage::Portage),
+    ]
+}
+
+pub fn for_system(system: &PackageSystem) -> Box<dyn PackageBackend> {
+    all()
+        .into_iter()
+        .find(|b| b.system() == *system)
+        .expect("every PackageSystem has a backend")
+}
+
+/// Package backends followed by the scanners that coexist with them.
+pub fn all_scanners() -> Vec<Box<dyn Scanner>> {
+    let mut scanners: Vec<Box<dyn Scanner>> = all().into_iter().map(|b| Box::new(b) as Box<dyn Scanner>).collect();
+    scanners.push(Box::new(flatpak::Flatpak));
+    scanners.push(Box::new(snap::Snap));
+    scanners.push(Box::new(nix::Nix));
+    scanners
+}
+
+/// Scanners to run: an explicit `[backends]` entry wins, then a configured
+/// `os` picks the single native backend, otherwise whatever is detected under `root`.
+pub fn enabled(config: &Config, root: &Path) -> Vec<Box<dyn Scanner>> {
+    all_scanners()
+        .into_iter()
+        .filter(|s| match config.backends.get(s.name()) {
+            Some(&enabled) => enabled,
+            None => match (&config.os, s.native_os()) {
+                (Some(os), Some(native)) => *os == native,
+                _ => s.detect(root),
+            },
+        })
+        .collect()
+}
+
+/// Which installed packages ship files in each per-application directory
+/// (see [`home_scanner::app_dir`]), so a directory shared with other
+/// packages is not taken as the name of one package's home directories.
+pub fn app_dir_owners(backend: &dyn PackageBackend, scope: &ScanScope) -> HashMap<PathBuf, HashSet<String>> {
+    let mut owners: HashMap<PathBuf, HashSet<String>> = HashMap::new();
+
+    for (pkg, files) in backend.installed_files(scope).unwrap_or_default() {
+        for file in files {
+            if let Some(dir) = home_scanner::app_dir(&file) {
+                owners.entry(dir).or_default().insert(pkg.clone());
+            }
+        }
+    }
+
+    owners
+}
+
+/// Recursively collects files under `root` whose names end with one of
+/// `suffixes`, paired with the matching suffix. Unreadable directories are skipped.
+pub fn find_files_with_suffix(root: &Path, suffixes: &[&'static str]) -> Vec<(PathBuf, &'static str)> {
+    let mut found = Vec::new();
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return found;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+
+        if file_type.is_dir() {
+            found.extend(find_files_with_suffix(&path, suffixes));
+        } else if let Some(name) = path.file_name().and_then(|s| s.to_str())
+            && let Some(suffix) = suffixes.iter().find(|s| name.ends_with(*s))
+        {
+            found.push((path, *suffix));
+        }
+    }
+
+    found
+}
+
+pub const UNMERGED_CONFIG: &str = "Unmerged config from a package upgrade";
+pub const SAVED_CONFIG: &str = "Config saved from a removed package";
+
+/// Files under `root/etc` ending in one of the suffixes, such as `.pacnew`,
+/// each with the reason paired with its suffix.
+pub fn find_config_leftovers(root: &Path, suffixes: &[(&'static str, &str)]) -> Vec<ConfigLeftover> {
+    let names: Vec<&'static str> = suffixes.iter().map(|(suffix, _)| *suffix).collect();
+
+    find_files_with_suffix(&root.join("etc"), &names)
+        .into_iter()
+        .map(|(path, suffix)| ConfigLeftover {
+            path,
+            reason: suffixes
+                .iter()
+                .find(|(s, _)| *s == suffix)
+                .map(|(_, reason)| reason.to_string())
+                .unwrap_or_default(),
+        })
+        .collect()
+}
+
+/// A package file found in a download cache.
+pub struct CachedPackage {
+    pub name: String,
+    pub version: String,
+    pub file: CacheFile,
+}
+
+/// Drops the `keep` newest versions of every package and returns the rest.
+pub fn prune_cache(mut cached: Vec<CachedPackage>, keep: usize) -> Vec<CacheFile> {
+    cached.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| compare_versions(&b.version, &a.version)));
+
+    let mut stale = Vec::new();
+    let mut seen = 0;
+    let mut current: Option<String> = None;
+
+    for pkg in cached {
+        if current.as_deref() != Some(pkg.name.as_str()) {
+            current = Some(pkg.name.clone());
+            seen = 0;
+        }
+
+        seen += 1;
+        if seen > keep {
+            stale.push(pkg.file);
+        }
+    }
+
+    stale
+}
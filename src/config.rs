@@ -2,15 +2,42 @@ use crate::types::OS;
 use serde::{Deserialize, Deserializer};
 use crossterm::event::KeyCode;
 use ratatui::style::Color;
+use std::collections::HashMap;
 
-#[derive(Debug, Clone, Default, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct Config {
     #[serde(default)]
     pub os: Option<OS>,
 
+    /// Packages that are never offered for removal; `*` and `?` wildcards allowed.
+    #[serde(default)]
+    pub protected_packages: Vec<String>,
+
+    /// Leave protected packages out of the list instead of greying them out.
+    #[serde(default)]
+    pub hide_protected: bool,
+
+    /// Force individual backends on or off by name, e.g. `snap = false`.
+    #[serde(default)]
+    pub backends: HashMap<String, bool>,
+
     #[serde(default = "default_su")]
     pub su_command: String,
 
+    /// Newest kernels that are never offered for removal.
+    #[serde(default = "default_keep_kernels")]
+    pub keep_kernels: usize,
+
+    /// Newest cached versions of each package kept in the download cache
+    /// besides the installed one, which is never offered.
+    #[serde(default = "default_keep_cached")]
+    pub keep_cached: usize,
+
+    /// Extra home paths per package, e.g. `firefox = ["~/.mozilla"]`,
+    /// added to the built-in table.
+    #[serde(default)]
+    pub known_apps: HashMap<String, Vec<String>>,
+
     #[serde(default)]
     pub theme: Theme,
 
@@ -18,7 +45,7 @@ pub struct Config {
     pub keybindings: Keybindings,
 }
 
-#[derive(Debug, Clone, Default, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct Theme {
     #[serde(default)]
     pub selected_bg: ColorDef,
@@ -28,6 +55,24 @@ pub struct Theme {
 
     #[serde(default = "default_artifact_icon")]
     pub artifact_icon: String,
+
+    #[serde(default = "default_leftover_icon")]
+    pub leftover_icon: String,
+
+    #[serde(default = "default_flatpak_icon")]
+    pub flatpak_icon: String,
+
+    #[serde(default = "default_snap_icon")]
+    pub snap_icon: String,
+
+    #[serde(default = "default_nix_icon")]
+    pub nix_icon: String,
+
+    #[serde(default = "default_cache_icon")]
+    pub cache_icon: String,
+
+    #[serde(default = "default_kernel_icon")]
+    pub kernel_icon: String,
 }
 
 #[derive(Debug, Clone)]
@@ -38,6 +83,7 @@ pub struct Keybindings {
     pub select_all: Vec<KeyCode>,
     pub cursor_up: Vec<KeyCode>,
     pub cursor_down: Vec<KeyCode>,
+    pub details: Vec<KeyCode>,
 }
 
 #[derive(Debug, Clone)]
@@ -59,6 +105,19 @@ impl Config {
     }
 }
 
+// Derived defaults would bypass the `default = "..."` functions below
+impl Default for Config {
+    fn default() -> Self {
+        toml::from_str("").expect("empty config uses serde defaults")
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        toml::from_str("").expect("empty theme uses serde defaults")
+    }
+}
+
 impl Default for ColorDef {
     fn default() -> Self {
         Self(Color::Green)
@@ -100,6 +159,9 @@ impl<'de> Deserialize<'de> for Keybindings {
 
             #[serde(default = "default_down")]
             cursor_down: Vec<String>,
+
+            #[serde(default = "default_details")]
+            details: Vec<String>,
         }
 
         let helper = Helper::deserialize(deserializer)?;
@@ -121,6 +183,7 @@ impl<'de> Deserialize<'de> for Keybindings {
             select_all: parse_vec(helper.select_all, "select_all")?,
             cursor_up: parse_vec(helper.cursor_up, "cursor_up")?,
             cursor_down: parse_vec(helper.cursor_down, "cursor_down")?,
+            details: parse_vec(helper.details, "details")?,
         })
     }
 }
@@ -134,10 +197,43 @@ impl Default for Keybindings {
             select_all: vec![KeyCode::Char('a')],
             cursor_up: vec![KeyCode::Up, KeyCode::Char('k')],
             cursor_down: vec![KeyCode::Down, KeyCode::Char('j')],
+            details: vec![KeyCode::Char('d')],
         }
     }
 }
 
+/// Shell-style wildcard match supporting `*` and `?`.
+pub fn matches_glob(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            },
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            },
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    n = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                },
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
 fn parse_keycode_str(s: &str) -> Result<KeyCode, String> {
     let s = s.trim().to_lowercase();
 
@@ -176,8 +272,7 @@ fn parse_color(s: &str) -> Result<Color, String> {
 
     // TODO: tailwind-like
 
-    if s.starts_with('#') {
-        let hex = &s[1..];
+    if let Some(hex) = s.strip_prefix('#') {
         match hex.len() {
             3 => {
                 let r = u8::from_str_radix(&hex[0..1].repeat(2), 16).map_err(|_| "invalid hex")?;
@@ -204,6 +299,15 @@ fn default_confirm() -> Vec<String> { vec!["enter".to_string()] }
 fn default_select_all() -> Vec<String> { vec!["a".to_string()] }
 fn default_up() -> Vec<String> { vec!["up".to_string(), "k".to_string()] }
 fn default_down() -> Vec<String> { vec!["down".to_string(), "j".to_string()] }
+fn default_details() -> Vec<String> { vec!["d".to_string()] }
 fn default_su() -> String { "sudo".to_string() }
+fn default_keep_kernels() -> usize { 2 }
+fn default_keep_cached() -> usize { 1 }
 fn default_package_icon() -> String { "📦".to_string() }
 fn default_artifact_icon() -> String { "🧩".to_string() }
+fn default_leftover_icon() -> String { "📝".to_string() }
+fn default_flatpak_icon() -> String { "🧊".to_string() }
+fn default_snap_icon() -> String { "🫧".to_string() }
+fn default_nix_icon() -> String { "❄️".to_string() }
+fn default_cache_icon() -> String { "💾".to_string() }
+fn default_kernel_icon() -> String { "🐧".to_string() }
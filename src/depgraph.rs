@@ -0,0 +1,93 @@
+use crate::types::Explanation;
+use std::collections::{HashMap, HashSet};
+
+/// Installed packages and who depends on whom, as far as a backend can tell.
+#[derive(Debug, Default)]
+pub struct DependencyGraph {
+    depends: HashMap<String, HashSet<String>>,
+    reverse: HashMap<String, HashSet<String>>,
+    auto_installed: HashSet<String>,
+    origins: HashMap<String, String>,
+}
+
+impl DependencyGraph {
+    pub fn add_package(&mut self, name: &str, auto_installed: bool) {
+        self.depends.entry(name.to_string()).or_default();
+        if auto_installed {
+            self.auto_installed.insert(name.to_string());
+        }
+    }
+
+    /// Records that `from` needs `to`. Both must be real, installed package names.
+    pub fn add_dependency(&mut self, from: &str, to: &str) {
+        if from == to {
+            return;
+        }
+
+        self.depends.entry(from.to_string()).or_default().insert(to.to_string());
+        self.reverse.entry(to.to_string()).or_default().insert(from.to_string());
+    }
+
+    /// Records what originally pulled `name` in, e.g. `apt install foo`.
+    pub fn set_origin(&mut self, name: &str, origin: &str) {
+        self.origins.insert(name.to_string(), origin.to_string());
+    }
+
+    pub fn required_by(&self, name: &str) -> Vec<String> {
+        let mut dependents: Vec<String> = self
+            .reverse
+            .get(name)
+            .map(|r| r.iter().cloned().collect())
+            .unwrap_or_default();
+        dependents.sort();
+        dependents
+    }
+
+    /// Auto-installed packages that would be left without dependents once
+    /// `name` is removed, following the chain as far as it goes.
+    pub fn cascade(&self, name: &str) -> Vec<String> {
+        let mut removed: HashSet<&str> = HashSet::from([name]);
+        let mut pending = vec![name];
+        let mut cascade = Vec::new();
+
+        while let Some(current) = pending.pop() {
+            let Some(deps) = self.depends.get(current) else {
+                continue;
+            };
+
+            for dep in deps {
+                if removed.contains(dep.as_str()) || !self.auto_installed.contains(dep) {
+                    continue;
+                }
+
+                let orphaned = self
+                    .reverse
+                    .get(dep)
+                    .is_none_or(|r| r.iter().all(|d| removed.contains(d.as_str())));
+
+                if orphaned {
+                    removed.insert(dep);
+                    pending.push(dep);
+                    cascade.push(dep.clone());
+                }
+            }
+        }
+
+        cascade.sort();
+        cascade
+    }
+
+    /// Why `name` was flagged, or `None` if it is not an installed package.
+    pub fn explain(&self, name: &str) -> Option<Explanation> {
+        if !self.depends.contains_key(name) {
+            return None;
+        }
+
+        Some(Explanation {
+            auto_installed: self.auto_installed.contains(name),
+            required_by: self.required_by(name),
+            pulled_in_by: self.origins.get(name).cloned(),
+            cascade: self.cascade(name),
+        })
+    }
+}
@@ -1,33 +1,655 @@
-use crate::types::{Package, PackageSystem};
+use crate::backend::{self, CachedPackage, PackageBackend, ScanScope};
+use crate::depgraph::DependencyGraph;
+use crate::kernel;
+use crate::types::{CacheFile, Kernel, Package, PackageSystem, OS};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
-pub fn list_residual_configs() -> anyhow::Result<Vec<Package>> {
-    let output = Command::new("dpkg")
-        .args(["-l"])
+const STATUS_PATH: &str = "var/lib/dpkg/status";
+const INFO_PATH: &str = "var/lib/dpkg/info";
+const ARCHIVES_PATH: &str = "var/cache/apt/archives";
+const EXTENDED_STATES_PATH: &str = "var/lib/apt/extended_states";
+const HISTORY_PATH: &str = "var/log/apt/history.log";
+
+pub struct Dpkg;
+
+/// One stanza of the dpkg status database.
+#[derive(Debug, Clone, Default)]
+pub struct StatusEntry {
+    pub name: String,
+    /// Last word of `Status:`, e.g. `installed` or `config-files`.
+    pub state: String,
+    pub version: String,
+    /// `Essential: yes` or `Priority: required`.
+    pub essential: bool,
+    /// Synopsis (first line) of `Description:`.
+    pub description: String,
+    pub conffiles: Vec<PathBuf>,
+    /// `Pre-Depends:` and `Depends:`, one list of alternatives per dependency.
+    pub depends: Vec<Vec<String>>,
+    /// Virtual packages listed in `Provides:`.
+    pub provides: Vec<String>,
+}
+
+impl PackageBackend for Dpkg {
+    fn name(&self) -> &'static str {
+        "dpkg"
+    }
+
+    fn os(&self) -> OS {
+        OS::Debian
+    }
+
+    fn system(&self) -> PackageSystem {
+        PackageSystem::Dpkg
+    }
+
+    fn detect(&self, root: &Path) -> bool {
+        root.join("usr/bin/dpkg").exists()
+    }
+
+    fn list_candidates(&self, scope: &ScanScope) -> anyhow::Result<Vec<Package>> {
+        let mut packages = Vec::new();
+
+        // apt-get and apt-mark only know the running system
+        if scope.orphans && scope.is_host() {
+            packages.extend(list_autoremove_candidates()?);
+        }
+        if scope.residual {
+            packages.extend(list_residual_configs(&scope.root)?);
+        }
+
+        Ok(packages)
+    }
+
+    fn removal_command(&self, pkgs: &[&Package]) -> Vec<String> {
+        let mut cmd = vec!["apt".to_string(), "purge".to_string(), "-y".to_string()];
+        cmd.extend(pkgs.iter().map(|p| p.name.clone()));
+        cmd
+    }
+
+    fn simulate_removal(&self, pkgs: &[&Package]) -> anyhow::Result<Option<Vec<String>>> {
+        let output = Command::new("apt-get")
+            .args(["-s", "purge"])
+            .args(pkgs.iter().map(|p| &p.name))
+            .output()?;
+
+        if !output.status.success() {
+            anyhow::bail!("apt-get -s purge failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+
+        Ok(Some(parse_simulation(&String::from_utf8(output.stdout)?)))
+    }
+
+    fn dependency_graph(&self, scope: &ScanScope) -> anyhow::Result<Option<DependencyGraph>> {
+        Ok(Some(read_dependency_graph(&scope.root)?))
+    }
+
+    fn essential_packages(&self, scope: &ScanScope) -> anyhow::Result<HashSet<String>> {
+        let essential = read_status(&scope.root)?
+            .into_iter()
+            .filter(|entry| entry.state == "installed" && entry.essential)
+            .map(|entry| entry.name)
+            .collect();
+
+        Ok(essential)
+    }
+
+    fn installed_packages(&self, scope: &ScanScope) -> anyhow::Result<Vec<String>> {
+        let installed = read_status(&scope.root)?
+            .into_iter()
+            .filter(|entry| entry.state == "installed")
+            .map(|entry| entry.name)
+            .collect();
+
+        Ok(installed)
+    }
+
+    fn package_files(&self, root: &Path, name: &str) -> anyhow::Result<Vec<PathBuf>> {
+        read_file_list(root, name)
+    }
+
+    fn installed_files(&self, scope: &ScanScope) -> anyhow::Result<Vec<(String, Vec<PathBuf>)>> {
+        let lists = index_file_lists(&scope.root)?;
+        let packages = self.installed_packages(scope)?.into_iter().map(|name| {
+            let files = lists.get(&name).and_then(|list| read_list(list).ok()).unwrap_or_default();
+            (name, files)
+        });
+
+        Ok(packages.collect())
+    }
+
+    fn list_kernels(&self, scope: &ScanScope) -> anyhow::Result<Vec<Kernel>> {
+        list_old_kernels(scope)
+    }
+
+    fn kernel_removal_command(&self, kernel: &Kernel) -> Vec<String> {
+        let mut command = vec!["apt".to_string(), "purge".to_string(), "-y".to_string()];
+        command.extend(kernel.packages.iter().cloned());
+        command
+    }
+
+    fn list_caches(&self, scope: &ScanScope) -> anyhow::Result<Vec<CacheFile>> {
+        Ok(list_stale_archives(scope))
+    }
+}
+
+/// Packages that were removed but not purged (`rc` in `dpkg -l`).
+pub fn list_residual_configs(root: &Path) -> anyhow::Result<Vec<Package>> {
+    let packages = read_status(root)?
+        .into_iter()
+        .filter(|entry| entry.state == "config-files")
+        .map(|entry| Package {
+            description: format!("Residual config, {} files — {}", entry.conffiles.len(), entry.description),
+            name: entry.name,
+            version: entry.version,
+            installed: false,
+            installed_size: None,
+            system: PackageSystem::Dpkg,
+            explanation: None,
+            protected: false,
+            category: None,
+        })
+        .collect();
+
+    Ok(packages)
+}
+
+/// Auto-installed packages nothing depends on anymore, i.e. what
+/// `apt-get autoremove` would remove. Packages marked with
+/// `apt-mark manual` are never reported.
+pub fn list_autoremove_candidates() -> anyhow::Result<Vec<Package>> {
+    let output = Command::new("apt-get")
+        .args(["-s", "autoremove"])
         .output()?;
 
     if !output.status.success() {
-        anyhow::bail!("dpkg failed");
+        anyhow::bail!("apt-get -s autoremove failed");
     }
 
     let stdout = String::from_utf8(output.stdout)?;
+    let manual = list_manual()?;
     let mut packages = Vec::new();
 
+    // Simulated removals look like: `Remv libfoo1 [1.2-3]`
     for line in stdout.lines() {
-        if line.starts_with("rc ") {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() >= 2 {
-                let name = parts[1].to_string();
-                packages.push(Package {
-                    name,
-                    version: "residual".to_string(),
-                    description: "Residual config".to_string(),
-                    installed: false,
-                    system: PackageSystem::Dpkg,
-                });
-            }
+        let Some(rest) = line.strip_prefix("Remv ") else {
+            continue;
+        };
+
+        let mut parts = rest.split_whitespace();
+        let Some(name) = parts.next() else {
+            continue;
+        };
+
+        // Old kernels are reported, with their files, by `list_old_kernels`
+        if manual.contains(name) || kernel_release(name).is_some() {
+            continue;
         }
+
+        let version = parts
+            .next()
+            .map(|v| v.trim_matches(['[', ']']).to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+
+        packages.push(Package {
+            name: name.to_string(),
+            version,
+            description: "Auto-installed, no longer required".to_string(),
+            installed: true,
+            installed_size: None,
+            system: PackageSystem::Dpkg,
+            explanation: None,
+            protected: false,
+            category: None,
+        });
     }
 
     Ok(packages)
 }
+
+/// Installed `linux-image-<release>` packages other than the running
+/// kernel and the newest `scope.keep_kernels`, together with the
+/// `linux-modules-*`/`linux-headers-*` packages of the same release.
+pub fn list_old_kernels(scope: &ScanScope) -> anyhow::Result<Vec<Kernel>> {
+    let installed: Vec<StatusEntry> = read_status(&scope.root)?
+        .into_iter()
+        .filter(|entry| entry.state == "installed")
+        .collect();
+
+    let releases: Vec<String> = installed
+        .iter()
+        .filter(|entry| entry.name.starts_with("linux-image-"))
+        .filter_map(|entry| kernel_release(&entry.name))
+        .map(str::to_string)
+        .collect();
+
+    let running = kernel::running_release(&scope.root)?;
+    let kernels = kernel::select_removable(releases, &running, scope.keep_kernels)
+        .into_iter()
+        .map(|release| {
+            let files = kernel::release_files(&scope.root, &release);
+            Kernel {
+                size: kernel::disk_usage(&files),
+                packages: installed
+                    .iter()
+                    .filter(|entry| kernel_release(&entry.name) == Some(release.as_str()))
+                    .map(|entry| entry.name.clone())
+                    .collect(),
+                release,
+                files,
+                system: PackageSystem::Dpkg,
+            }
+        })
+        .collect();
+
+    Ok(kernels)
+}
+
+/// `linux-image-6.1.0-13-amd64` and `linux-modules-extra-6.1.0-13-amd64`
+/// both belong to release `6.1.0-13-amd64`; meta packages such as
+/// `linux-image-amd64` belong to none.
+fn kernel_release(package: &str) -> Option<&str> {
+    let rest = package.strip_prefix("linux-")?;
+    ["image-unsigned-", "image-", "modules-extra-", "modules-", "headers-"]
+        .iter()
+        .find_map(|prefix| rest.strip_prefix(prefix))
+        .filter(|release| release.starts_with(|c: char| c.is_ascii_digit()))
+}
+
+fn list_manual() -> anyhow::Result<HashSet<String>> {
+    let output = Command::new("apt-mark")
+        .args(["showmanual"])
+        .output()?;
+
+    if !output.status.success() {
+        anyhow::bail!("apt-mark showmanual failed");
+    }
+
+    let stdout = String::from_utf8(output.stdout)?;
+    Ok(stdout.lines().map(|l| l.trim().to_string()).collect())
+}
+
+/// Downloaded `<name>_<version>_<arch>.deb` files in the apt cache whose
+/// version is not installed, minus the newest `scope.keep_cached` of each package.
+pub fn list_stale_archives(scope: &ScanScope) -> Vec<CacheFile> {
+    let Ok(entries) = std::fs::read_dir(scope.root.join(ARCHIVES_PATH)) else {
+        return Vec::new();
+    };
+
+    let installed: HashMap<String, String> = read_status(&scope.root)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|e| e.state == "installed")
+        .map(|e| (e.name, e.version))
+        .collect();
+
+    let cached = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let stem = path.file_name()?.to_str()?.strip_suffix(".deb")?.to_string();
+            let mut parts = stem.split('_');
+            let (name, version) = (parts.next()?, parts.next()?);
+            // Epochs are escaped in file names: `1%3a2.0-1`
+            let version = version.replace("%3a", ":");
+
+            if installed.get(name).is_some_and(|v| *v == version) {
+                return None;
+            }
+
+            Some(CachedPackage {
+                name: name.to_string(),
+                version,
+                file: CacheFile {
+                    size: entry.metadata().map(|m| m.len()).unwrap_or(0),
+                    path,
+                    related: Vec::new(),
+                    reason: "Downloaded archive of a version no longer installed".to_string(),
+                },
+            })
+        })
+        .collect();
+
+    backend::prune_cache(cached, scope.keep_cached)
+}
+
+/// Builds the graph of installed packages from the status database, with
+/// auto-installed flags from apt's `extended_states` and origins from its
+/// history log. Alternatives and virtual packages count as dependencies on
+/// every installed candidate, so nothing is reported as orphaned too eagerly.
+pub fn read_dependency_graph(root: &Path) -> anyhow::Result<DependencyGraph> {
+    let installed: Vec<StatusEntry> = read_status(root)?
+        .into_iter()
+        .filter(|entry| entry.state == "installed")
+        .collect();
+
+    let mut providers: HashMap<&str, Vec<&str>> = HashMap::new();
+    for entry in &installed {
+        providers.entry(&entry.name).or_default().push(&entry.name);
+        for virtual_name in &entry.provides {
+            providers.entry(virtual_name).or_default().push(&entry.name);
+        }
+    }
+
+    let auto_installed = read_auto_installed(root);
+    let mut graph = DependencyGraph::default();
+
+    for entry in &installed {
+        graph.add_package(&entry.name, auto_installed.contains(&entry.name));
+
+        for dep in entry.depends.iter().flatten() {
+            for provider in providers.get(dep.as_str()).into_iter().flatten() {
+                graph.add_dependency(&entry.name, provider);
+            }
+        }
+    }
+
+    for (name, origin) in read_install_origins(root) {
+        graph.set_origin(&name, &origin);
+    }
+
+    Ok(graph)
+}
+
+/// Packages apt marked `Auto-Installed: 1`. A missing file means none are.
+fn read_auto_installed(root: &Path) -> HashSet<String> {
+    let Ok(contents) = std::fs::read_to_string(root.join(EXTENDED_STATES_PATH)) else {
+        return HashSet::new();
+    };
+
+    contents
+        .split("\n\n")
+        .filter_map(|stanza| {
+            let fields = parse_fields(stanza);
+            let field = |key: &str| fields.iter().find(|(k, _)| *k == key).map(|(_, v)| v.as_str());
+
+            if field("Auto-Installed") != Some("1") {
+                return None;
+            }
+            field("Package").map(str::to_string)
+        })
+        .collect()
+}
+
+/// Maps each package apt installed as `automatic` to the command line of
+/// that transaction, e.g. `apt install gimp`. Later transactions win.
+fn read_install_origins(root: &Path) -> HashMap<String, String> {
+    let Ok(contents) = std::fs::read_to_string(root.join(HISTORY_PATH)) else {
+        return HashMap::new();
+    };
+
+    let mut origins = HashMap::new();
+    for stanza in contents.split("\n\n") {
+        let fields = parse_fields(stanza);
+        let field = |key: &str| fields.iter().find(|(k, _)| *k == key).map(|(_, v)| v.as_str());
+
+        let (Some(commandline), Some(installed)) = (field("Commandline"), field("Install")) else {
+            continue;
+        };
+
+        // `Install: libfoo1:amd64 (1.2-3, automatic), gimp:amd64 (2.10-1)`
+        for item in installed.split("), ") {
+            let Some((package, details)) = item.split_once(" (") else {
+                continue;
+            };
+
+            if details.contains("automatic") {
+                let name = package.split(':').next().unwrap_or(package);
+                origins.insert(name.to_string(), commandline.to_string());
+            }
+        }
+    }
+
+    origins
+}
+
+/// Paths from `info/<name>.list`, or `info/<name>:<arch>.list` for
+/// multi-arch packages. Directories are listed too.
+pub fn read_file_list(root: &Path, name: &str) -> anyhow::Result<Vec<PathBuf>> {
+    let list = root.join(INFO_PATH).join(format!("{}.list", name));
+
+    if list.exists() {
+        return read_list(&list);
+    }
+
+    match index_file_lists(root)?.get(name) {
+        Some(qualified) => read_list(qualified),
+        None => anyhow::bail!("no file list for {}", name),
+    }
+}
+
+/// Every `info/*.list` by package name, from a single read of the directory,
+/// so listing all packages does not read it again for each multi-arch one.
+fn index_file_lists(root: &Path) -> anyhow::Result<HashMap<String, PathBuf>> {
+    let mut lists = HashMap::new();
+
+    for path in std::fs::read_dir(root.join(INFO_PATH))?.flatten().map(|e| e.path()) {
+        let Some(stem) = path.file_name().and_then(|s| s.to_str()).and_then(|s| s.strip_suffix(".list")) else {
+            continue;
+        };
+
+        match stem.split_once(':') {
+            Some((name, _)) => {
+                lists.entry(name.to_string()).or_insert(path);
+            },
+            // An unqualified list wins over an arch-qualified one
+            None => {
+                lists.insert(stem.to_string(), path);
+            },
+        }
+    }
+
+    Ok(lists)
+}
+
+fn read_list(list: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let files = std::fs::read_to_string(list)?
+        .lines()
+        .filter(|line| *line != "/.")
+        .map(PathBuf::from)
+        .collect();
+
+    Ok(files)
+}
+
+pub fn read_status(root: &Path) -> anyhow::Result<Vec<StatusEntry>> {
+    let contents = std::fs::read_to_string(root.join(STATUS_PATH))?;
+    Ok(parse_status(&contents))
+}
+
+/// Parses the deb822 stanzas of `/var/lib/dpkg/status`.
+pub fn parse_status(contents: &str) -> Vec<StatusEntry> {
+    let mut entries = Vec::new();
+
+    for stanza in contents.split("\n\n") {
+        let mut entry = StatusEntry::default();
+
+        for (key, value) in parse_fields(stanza) {
+            match key {
+                "Package" => entry.name = value.trim().to_string(),
+                "Status" => entry.state = value.split_whitespace().last().unwrap_or("").to_string(),
+                "Version" => entry.version = value.trim().to_string(),
+                "Essential" => entry.essential |= value.trim() == "yes",
+                "Priority" => entry.essential |= value.trim() == "required",
+                "Description" => entry.description = value.lines().next().unwrap_or("").trim().to_string(),
+                "Conffiles" => {
+                    entry.conffiles = value
+                        .lines()
+                        .filter_map(|l| l.split_whitespace().next())
+                        .map(PathBuf::from)
+                        .collect();
+                },
+                "Pre-Depends" | "Depends" => entry.depends.extend(parse_depends(&value)),
+                "Provides" => entry.provides = parse_depends(&value).into_iter().flatten().collect(),
+                _ => {},
+            }
+        }
+
+        if !entry.name.is_empty() {
+            entries.push(entry);
+        }
+    }
+
+    entries
+}
+
+/// Splits a stanza into `(field, value)` pairs, folding continuation
+/// lines (those starting with whitespace) into the preceding value.
+fn parse_fields(stanza: &str) -> Vec<(&str, String)> {
+    let mut fields: Vec<(&str, String)> = Vec::new();
+
+    for line in stanza.lines() {
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = fields.last_mut() {
+                value.push('\n');
+                value.push_str(line.trim());
+            }
+        } else if let Some((key, value)) = line.split_once(':') {
+            fields.push((key, value.trim().to_string()));
+        }
+    }
+
+    fields
+}
+
+/// `libc6 (>= 2.34), default-mta | mail-transport-agent, python3:any`
+/// becomes `[[libc6], [default-mta, mail-transport-agent], [python3]]`.
+fn parse_depends(value: &str) -> Vec<Vec<String>> {
+    value
+        .split(',')
+        .map(|group| {
+            group
+                .split('|')
+                .filter_map(|alt| alt.split_whitespace().next())
+                .map(|name| name.split(':').next().unwrap_or(name).to_string())
+                .collect::<Vec<_>>()
+        })
+        .filter(|group| !group.is_empty())
+        .collect()
+}
+
+/// Packages an `apt-get -s` run would take out: `Purg nano [7.2-1]` or
+/// `Remv nano [7.2-1]` lines.
+fn parse_simulation(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| line.strip_prefix("Purg ").or_else(|| line.strip_prefix("Remv ")))
+        .filter_map(|rest| rest.split_whitespace().next())
+        .map(|name| name.split(':').next().unwrap_or(name).to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATUS: &str = include_str!("../tests/fixtures/dpkg/root/var/lib/dpkg/status");
+
+    #[test]
+    fn parses_every_stanza() {
+        let names: Vec<_> = parse_status(STATUS).into_iter().map(|e| e.name).collect();
+        assert_eq!(names, ["libc6", "nano", "oldapp", "mailutils", "postfix", "dpkg"]);
+    }
+
+    #[test]
+    fn reads_version_state_and_synopsis() {
+        let entries = parse_status(STATUS);
+        let nano = entries.iter().find(|e| e.name == "nano").unwrap();
+
+        assert_eq!(nano.state, "installed");
+        assert_eq!(nano.version, "7.2-1+deb12u1");
+        assert_eq!(nano.description, "small, friendly text editor inspired by Pico");
+    }
+
+    #[test]
+    fn parses_apt_simulation() {
+        let output = "\
+NOTE: This is only a simulation!
+Reading package lists...
+The following packages will be REMOVED:
+  mailutils* postfix*
+Purg mailutils [1:3.15-4]
+Purg postfix [3.7.11-0+deb12u1]
+Remv libgsasl18:amd64 [2.2.0-1]
+";
+
+        assert_eq!(parse_simulation(output), ["mailutils", "postfix", "libgsasl18"]);
+    }
+
+    #[test]
+    fn reads_plain_and_multiarch_file_lists() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/dpkg/root");
+
+        let nano = read_file_list(&root, "nano").unwrap();
+        assert_eq!(nano.first(), Some(&PathBuf::from("/bin")));
+        assert!(nano.contains(&PathBuf::from("/usr/share/applications/nano.desktop")));
+
+        let libc = read_file_list(&root, "libc6").unwrap();
+        assert!(libc.contains(&PathBuf::from("/lib/x86_64-linux-gnu/libc.so.6")));
+
+        let lists = index_file_lists(&root).unwrap();
+        assert_eq!(lists["nano"], root.join(INFO_PATH).join("nano.list"));
+        assert!(lists["libc6"].ends_with("libc6:amd64.list"));
+    }
+
+    #[test]
+    fn marks_essential_and_required_packages() {
+        let entries = parse_status(STATUS);
+        let essential: Vec<_> = entries.iter().filter(|e| e.essential).map(|e| e.name.as_str()).collect();
+
+        assert_eq!(essential, ["libc6", "dpkg"]);
+    }
+
+    #[test]
+    fn reads_residual_conffiles() {
+        let entries = parse_status(STATUS);
+        let oldapp = entries.iter().find(|e| e.name == "oldapp").unwrap();
+
+        assert_eq!(oldapp.state, "config-files");
+        assert_eq!(
+            oldapp.conffiles,
+            [PathBuf::from("/etc/oldapp/oldapp.conf"), PathBuf::from("/etc/default/oldapp")]
+        );
+    }
+
+    #[test]
+    fn groups_dependency_alternatives() {
+        let entries = parse_status(STATUS);
+        let mailutils = entries.iter().find(|e| e.name == "mailutils").unwrap();
+
+        assert_eq!(
+            mailutils.depends,
+            [
+                vec!["libc6".to_string()],
+                vec!["libc6".to_string()],
+                vec!["default-mta".to_string(), "mail-transport-agent".to_string()],
+                vec!["python3".to_string()],
+            ]
+        );
+    }
+
+    #[test]
+    fn cascades_through_auto_installed_dependencies() {
+        let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/dpkg/root");
+        let graph = read_dependency_graph(&root).unwrap();
+        let why = graph.explain("mailutils").unwrap();
+
+        assert!(why.auto_installed);
+        assert!(why.required_by.is_empty());
+        assert_eq!(why.pulled_in_by.as_deref(), Some("apt install mailutils-helper"));
+        assert_eq!(why.cascade, ["postfix"]);
+    }
+
+    #[test]
+    fn residual_configs_under_root() {
+        let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/dpkg/root");
+        let residual = list_residual_configs(&root).unwrap();
+
+        assert_eq!(residual.len(), 1);
+        assert_eq!(residual[0].name, "oldapp");
+        assert_eq!(residual[0].version, "1.4-2");
+        assert!(!residual[0].installed);
+    }
+}
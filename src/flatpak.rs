@@ -0,0 +1,312 @@
+use crate::backend::{ScanScope, Scanner};
+use crate::types::{FlatpakInstallation, FlatpakLeftover, SweepItem};
+use std::collections::HashSet;
+use std::process::Command;
+
+pub struct Flatpak;
+
+impl Scanner for Flatpak {
+    fn name(&self) -> &'static str {
+        "flatpak"
+    }
+
+    fn detect(&self, _root: &std::path::Path) -> bool {
+        std::path::Path::new("/usr/bin/flatpak").exists()
+    }
+
+    fn supports_root(&self) -> bool {
+        false
+    }
+
+    fn scan(&self, scope: &ScanScope) -> anyhow::Result<Vec<SweepItem>> {
+        if !scope.orphans {
+            return Ok(Vec::new());
+        }
+
+        Ok(list_leftovers()?.into_iter().map(SweepItem::Flatpak).collect())
+    }
+
+    /// App IDs, plus their last component for apps that write to the
+    /// host's XDG directories (`org.mozilla.firefox` -> `firefox`).
+    fn installed_names(&self, _scope: &ScanScope) -> anyhow::Result<HashSet<String>> {
+        let mut names = HashSet::new();
+
+        for app in list_installed_apps()? {
+            let app = app.to_lowercase();
+            if let Some(last) = app.rsplit('.').next() {
+                names.insert(last.to_string());
+            }
+            names.insert(app);
+        }
+
+        Ok(names)
+    }
+}
+
+/// Unused runtimes/extensions of both installations plus `~/.var/app`
+/// directories of apps that are no longer installed.
+pub fn list_leftovers() -> anyhow::Result<Vec<FlatpakLeftover>> {
+    // Apps of either installation may run on a runtime of the other one
+    let used = list_used_runtimes()?;
+    let mut leftovers = Vec::new();
+
+    for installation in [FlatpakInstallation::User, FlatpakInstallation::System] {
+        leftovers.extend(list_unused(installation, &used)?);
+    }
+
+    leftovers.extend(list_orphaned_app_data()?);
+
+    Ok(leftovers)
+}
+
+/// What `flatpak uninstall --unused` would remove: runtimes outside `used`
+/// that are not pinned either. Worked out without running the uninstall.
+fn list_unused(
+    installation: FlatpakInstallation,
+    used: &HashSet<(String, String)>,
+) -> anyhow::Result<Vec<FlatpakLeftover>> {
+    let pins = list_pins(installation)?;
+
+    let unused = flatpak_list(&["--runtime", installation.flag(), "--columns=application,branch"])?
+        .into_iter()
+        .filter_map(|row| {
+            let [id, branch] = <[String; 2]>::try_from(row).ok()?;
+            let kept = used.contains(&(id.clone(), branch.clone()))
+                || pins.iter().any(|pin| pin_matches(pin, &id, &branch));
+
+            (!kept).then_some(FlatpakLeftover::UnusedRef { id, branch, installation })
+        })
+        .collect();
+
+    Ok(unused)
+}
+
+/// `(id, branch)` of every installed runtime that an installed app needs,
+/// directly or through the extension points of its metadata and of the
+/// runtimes it uses (GL drivers, codecs, themes, locales).
+fn list_used_runtimes() -> anyhow::Result<HashSet<(String, String)>> {
+    let runtimes = flatpak_list(&["--runtime", "--columns=application,branch"])?;
+    let mut pending = flatpak_list(&["--app", "--columns=application,branch"])?;
+    let mut used = HashSet::new();
+
+    while let Some(row) = pending.pop() {
+        let [id, branch] = row.as_slice() else {
+            continue;
+        };
+        let output = Command::new("flatpak")
+            .args(["info", "-m", &format!("{}//{}", id, branch)])
+            .output()?;
+        let metadata = String::from_utf8_lossy(&output.stdout);
+        let metadata = parse_metadata(&metadata, branch);
+
+        let extensions = runtimes.iter().filter_map(|runtime| match runtime.as_slice() {
+            [id, branch] if metadata.extension_points.iter().any(|point| point.matches(id, branch)) => {
+                Some((id.clone(), branch.clone()))
+            },
+            _ => None,
+        });
+
+        for runtime in metadata.runtimes.into_iter().chain(extensions.collect::<Vec<_>>()) {
+            if used.insert(runtime.clone()) {
+                pending.push(vec![runtime.0, runtime.1]);
+            }
+        }
+    }
+
+    Ok(used)
+}
+
+/// The parts of a `flatpak info -m` keyfile that keep other refs installed.
+struct Metadata {
+    /// `runtime=` and `sdk=` of the `[Application]` or `[Runtime]` group.
+    runtimes: Vec<(String, String)>,
+    extension_points: Vec<ExtensionPoint>,
+}
+
+/// An `[Extension <id>]` group.
+struct ExtensionPoint {
+    id: String,
+    /// `versions=` wins over `version=`, which wins over the declaring ref's branch.
+    versions: Option<Vec<String>>,
+    version: String,
+    subdirectories: bool,
+}
+
+impl ExtensionPoint {
+    /// With `subdirectories=true`, `org.freedesktop.Platform.GL` also takes
+    /// `org.freedesktop.Platform.GL.default` and `GL.nvidia-*`.
+    fn matches(&self, id: &str, branch: &str) -> bool {
+        let id_matches = id == self.id
+            || (self.subdirectories && id.strip_prefix(self.id.as_str()).is_some_and(|rest| rest.starts_with('.')));
+
+        id_matches
+            && match &self.versions {
+                Some(versions) => versions.iter().any(|v| v == branch),
+                None => self.version == branch,
+            }
+    }
+}
+
+fn parse_metadata(metadata: &str, branch: &str) -> Metadata {
+    let mut parsed = Metadata { runtimes: Vec::new(), extension_points: Vec::new() };
+    let mut group = "";
+
+    for line in metadata.lines().map(str::trim) {
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            group = name;
+            if let Some(id) = name.strip_prefix("Extension ") {
+                parsed.extension_points.push(ExtensionPoint {
+                    id: id.to_string(),
+                    versions: None,
+                    version: branch.to_string(),
+                    subdirectories: false,
+                });
+            }
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+
+        match (group, key) {
+            ("Application" | "Runtime", "runtime" | "sdk") => {
+                // `org.gnome.Platform/x86_64/46`
+                let mut parts = value.split('/');
+                if let (Some(id), Some(branch)) = (parts.next(), parts.next_back()) {
+                    parsed.runtimes.push((id.to_string(), branch.to_string()));
+                }
+            },
+            (group, key) if group.starts_with("Extension ") => {
+                let Some(point) = parsed.extension_points.last_mut() else {
+                    continue;
+                };
+                match key {
+                    "version" => point.version = value.to_string(),
+                    "versions" => point.versions = Some(value.split(';').filter(|v| !v.is_empty()).map(str::to_string).collect()),
+                    "subdirectories" => point.subdirectories = value == "true",
+                    _ => {},
+                }
+            },
+            _ => {},
+        }
+    }
+
+    parsed
+}
+
+/// Patterns from `flatpak pin`, e.g. `runtime/org.freedesktop.Sdk/x86_64/23.08`.
+/// Explicitly installed runtimes are pinned automatically.
+fn list_pins(installation: FlatpakInstallation) -> anyhow::Result<Vec<String>> {
+    let output = Command::new("flatpak").args(["pin", installation.flag()]).output()?;
+
+    // Flatpak before 1.9 has no pins, and no runtimes are pinned there
+    if !output.status.success() {
+        return Ok(Vec::new());
+    }
+
+    let stdout = String::from_utf8(output.stdout)?;
+    Ok(stdout
+        .lines()
+        .filter_map(|l| l.trim().strip_prefix("runtime/"))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Matches `<id>/<arch>/<branch>` where any part may be `*` or left out.
+fn pin_matches(pin: &str, id: &str, branch: &str) -> bool {
+    let parts: Vec<&str> = pin.split('/').collect();
+    let matches = |pattern: Option<&&str>, value: &str| pattern.is_none_or(|p| *p == "*" || *p == value);
+
+    matches(parts.first(), id) && matches(parts.get(2), branch)
+}
+
+/// Tab-separated rows of `flatpak list` with the given arguments.
+fn flatpak_list(args: &[&str]) -> anyhow::Result<Vec<Vec<String>>> {
+    let output = Command::new("flatpak").arg("list").args(args).output()?;
+
+    if !output.status.success() {
+        anyhow::bail!("flatpak list failed");
+    }
+
+    let stdout = String::from_utf8(output.stdout)?;
+    Ok(stdout
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.split('\t').map(|col| col.trim().to_string()).collect())
+        .collect())
+}
+
+fn list_orphaned_app_data() -> anyhow::Result<Vec<FlatpakLeftover>> {
+    let Some(home) = dirs::home_dir() else {
+        return Ok(Vec::new());
+    };
+
+    let Ok(entries) = std::fs::read_dir(home.join(".var/app")) else {
+        return Ok(Vec::new());
+    };
+
+    let installed = list_installed_apps()?;
+    let mut leftovers = Vec::new();
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let app_id = path.file_name().and_then(|s| s.to_str()).unwrap_or("").to_string();
+
+        if !app_id.is_empty() && !installed.contains(&app_id) {
+            leftovers.push(FlatpakLeftover::AppData { app_id, path });
+        }
+    }
+
+    Ok(leftovers)
+}
+
+fn list_installed_apps() -> anyhow::Result<HashSet<String>> {
+    Ok(flatpak_list(&["--app", "--columns=application"])?
+        .into_iter()
+        .filter_map(|row| row.into_iter().next())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GNOME_PLATFORM: &str = "\
+[Runtime]
+name=org.gnome.Platform
+runtime=org.gnome.Platform/x86_64/46
+sdk=org.gnome.Sdk/x86_64/46
+
+[Extension org.freedesktop.Platform.GL]
+version=1.4
+versions=23.08;1.4
+directory=lib/x86_64-linux-gnu/GL
+subdirectories=true
+
+[Extension org.freedesktop.Platform.openh264]
+directory=lib/x86_64-linux-gnu/openh264
+version=2.2.0
+
+[Extension org.gnome.Platform.Locale]
+directory=share/runtime/locale
+";
+
+    #[test]
+    fn inherited_extension_points_keep_their_extensions() {
+        let metadata = parse_metadata(GNOME_PLATFORM, "46");
+
+        assert_eq!(
+            metadata.runtimes,
+            [("org.gnome.Platform".to_string(), "46".to_string()), ("org.gnome.Sdk".to_string(), "46".to_string())]
+        );
+
+        let used = |id: &str, branch: &str| metadata.extension_points.iter().any(|p| p.matches(id, branch));
+        assert!(used("org.freedesktop.Platform.GL.default", "23.08"));
+        assert!(used("org.freedesktop.Platform.GL.nvidia-550-54-14", "1.4"));
+        assert!(!used("org.freedesktop.Platform.GL.default", "22.08"));
+        assert!(used("org.freedesktop.Platform.openh264", "2.2.0"));
+        assert!(used("org.gnome.Platform.Locale", "46"));
+        assert!(!used("org.gnome.Platform.Locale.extra", "46"));
+    }
+}
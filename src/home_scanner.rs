@@ -1,31 +1,572 @@
 use crate::types::HomeArtifact;
+use std::collections::{HashMap, HashSet};
+use std::os::unix::ffi::OsStringExt;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
-pub fn find_suspicious_artifacts(removed_packages: &[String]) -> Vec<HomeArtifact> {
+/// Directories whose entries are named after a program.
+const BIN_DIRS: &[&str] = &["/bin", "/sbin", "/usr/bin", "/usr/sbin", "/usr/games", "/usr/local/bin"];
+
+/// Directories with one subdirectory per application.
+const APP_DIRS: &[&str] = &["/usr/share", "/usr/lib", "/usr/lib64", "/usr/libexec", "/opt"];
+
+/// Subdirectories of [`APP_DIRS`] shared by many packages, which say
+/// nothing about the application's own home directories.
+const SHARED_DIRS: &[&str] = &[
+    "applications", "bash-completion", "dbus-1", "doc", "fish", "fonts", "glib-2.0",
+    "gtk-2.0", "gtk-3.0", "gtk-4.0", "gtk-doc", "icons", "info", "licenses", "lintian",
+    "locale", "man", "menu", "metainfo", "mime", "mozilla", "pixmaps", "pkgconfig",
+    "polkit-1", "sounds", "systemd", "themes", "zsh",
+];
+
+/// Home entries that every desktop session creates and no single
+/// package owns, never offered by name. Dot-entries of `$HOME`
+/// managed by flatpak and nix are covered by those scanners instead.
+const SHARED_ENTRIES: &[&str] = &[
+    ".dbus", ".nix-channels", ".nix-defexpr", ".nix-profile", ".sudo_as_admin_successful",
+    ".trash", ".var", ".xsession-errors",
+    "applications", "autostart", "dconf", "desktop-directories", "environment.d", "fontconfig",
+    "fonts", "gtk-2.0", "gtk-3.0", "gtk-4.0", "icons", "keyrings", "menus", "mime",
+    "mimeapps.list", "pulse", "recently-used.xbel", "sessions", "sweep", "systemd", "themes",
+    "thumbnails", "trash", "user-dirs.dirs", "user-dirs.locale",
+];
+
+/// Dot-entries of `$HOME` that hold the user's own shell, VCS, ssh and gpg
+/// setup. They are never offered, whatever package they seem to belong to.
+const HOME_SAFELIST: &[&str] = &[
+    ".bash_aliases", ".bash_history", ".bash_logout", ".bash_profile", ".bashrc", ".cache",
+    ".config", ".cshrc", ".dmrc", ".editorconfig", ".face", ".git-credentials", ".gitattributes",
+    ".gitconfig", ".gitignore", ".gnupg", ".hgrc", ".ICEauthority", ".inputrc", ".kshrc",
+    ".local", ".mkshrc", ".netrc", ".pam_environment", ".password-store", ".pki", ".profile",
+    ".ssh", ".tcshrc", ".Xauthority", ".Xdefaults", ".xinitrc", ".Xmodmap", ".xprofile",
+    ".Xresources", ".xsession", ".xsessionrc", ".zlogin", ".zlogout", ".zprofile", ".zsh_history",
+    ".zshenv", ".zshrc",
+];
+
+/// Entries touched more recently than this are probably still in use.
+const RECENT_DAYS: u64 = 30;
+
+/// A package whose leftovers may be lying around in the home directory.
+pub struct PackageFootprint {
+    pub name: String,
+    /// Files the package installed, as its package manager lists them.
+    pub files: Vec<PathBuf>,
+}
+
+/// Home paths of `removed_packages`: first those listed for them in
+/// `known_apps` (unless a package in `installed` that stays lists them too),
+/// then XDG directory entries (runtime ones only if unused) and `$HOME`
+/// dot-entries named like the package.
+pub fn find_suspicious_artifacts(
+    removed_packages: &[PackageFootprint],
+    known_apps: &HashMap<String, Vec<PathBuf>>,
+    installed: &HashSet<String>,
+) -> Vec<HomeArtifact> {
     let mut artifacts = Vec::new();
     let home = dirs::home_dir().expect("no home dir");
 
+    for pkg in removed_packages {
+        let Some(paths) = known_apps.get(&pkg.name) else {
+            continue;
+        };
+
+        for path in paths {
+            // `~/.mozilla` of a purged `firefox-esr` is the live profile of an installed `firefox`
+            let kept = known_apps.iter().any(|(other, other_paths)| {
+                other_paths.contains(path)
+                    && installed.contains(&other.to_lowercase())
+                    && !removed_packages.iter().any(|p| p.name == *other)
+            });
+
+            if path.exists()
+                && !kept
+                && !is_safelisted(path, &home)
+                && !artifacts.iter().any(|a: &HomeArtifact| a.path == *path)
+            {
+                artifacts.push(HomeArtifact {
+                    path: path.clone(),
+                    associated_package: Some(pkg.name.clone()),
+                    reason: format!("Known home path of {}", pkg.name),
+                    confidence: 95,
+                });
+            }
+        }
+    }
+
+    let expected: Vec<(&str, Vec<(String, String)>)> = removed_packages
+        .iter()
+        .map(|pkg| (pkg.name.as_str(), expected_names(&pkg.name, &pkg.files)))
+        .collect();
+
     let xdg_dirs = [
         dirs::config_dir().unwrap_or(home.join(".config")),
         dirs::data_dir().unwrap_or(home.join(".local/share")),
         dirs::cache_dir().unwrap_or(home.join(".cache")),
+        dirs::state_dir().unwrap_or(home.join(".local/state")),
     ];
 
-    for base in xdg_dirs {
-        if let Ok(entries) = std::fs::read_dir(&base) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                let name = path.file_name().and_then(|s| s.to_str()).unwrap_or("").to_string();
-
-                if removed_packages.contains(&name) {
-                    artifacts.push(HomeArtifact {
-                        path,
-                        associated_package: Some(name),
-                        reason: "Matches removed package name".to_string(),
-                    });
-                }
+    for path in scan_entries(&xdg_dirs, &home).into_iter().chain(stale_runtime_entries()) {
+        let name = path.file_name().and_then(|s| s.to_str()).unwrap_or("").to_lowercase();
+        // `/usr/bin/dconf` from `dconf-cli` must not put the GNOME settings database up for removal
+        if SHARED_ENTRIES.contains(&name.as_str()) {
+            continue;
+        }
+
+        let keys = lookup_keys(&name);
+
+        let owner = expected.iter().find_map(|(pkg, names)| {
+            names.iter().find(|(expected, _)| keys.contains(expected)).map(|(expected, why)| (pkg, expected, why))
+        });
+
+        if let Some((pkg, expected, why)) = owner
+            && !artifacts.iter().any(|a| a.path == path)
+        {
+            artifacts.push(HomeArtifact {
+                path,
+                associated_package: Some(pkg.to_string()),
+                reason: why.clone(),
+                // A name taken from a file list is a weaker hint than the package name
+                confidence: if *expected == pkg.to_lowercase() { 90 } else { 75 },
+            });
+        }
+    }
+
+    artifacts
+}
+
+/// Deep scan: every entry of the XDG config, data, cache and state
+/// directories, and every `$HOME` dot-entry, whose name matches nothing in
+/// `owners` (names installed through each scanner, keyed by scanner name)
+/// nor any binary on `$PATH`. Entries `known_apps` lists are attributed to
+/// their package, and skipped if any package listing them is installed.
+/// `$XDG_RUNTIME_DIR` entries are offered whenever no running process uses them.
+pub fn find_unowned_artifacts(
+    owners: &[(&str, HashSet<String>)],
+    known_apps: &HashMap<String, Vec<PathBuf>>,
+) -> Vec<HomeArtifact> {
+    let mut artifacts = Vec::new();
+    let home = dirs::home_dir().expect("no home dir");
+    let binaries = path_binaries();
+
+    let mut checks: Vec<&str> = owners.iter().map(|(scanner, _)| *scanner).collect();
+    checks.push("any $PATH binary");
+    let checks = match checks.split_last() {
+        Some((last, [])) => last.to_string(),
+        Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+        None => String::new(),
+    };
+
+    let xdg_dirs = [
+        dirs::config_dir().unwrap_or(home.join(".config")),
+        dirs::data_dir().unwrap_or(home.join(".local/share")),
+        dirs::cache_dir().unwrap_or(home.join(".cache")),
+        dirs::state_dir().unwrap_or(home.join(".local/state")),
+    ];
+
+    for path in scan_entries(&xdg_dirs, &home) {
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_lowercase();
+        if SHARED_ENTRIES.contains(&name.as_str()) {
+            continue;
+        }
+
+        let keys = lookup_keys(&name);
+        let owned = |names: &HashSet<String>| keys.iter().any(|key| names.contains(key));
+        if owned(&binaries) || owners.iter().any(|(_, names)| owned(names)) {
+            continue;
+        }
+
+        // `~/.mozilla` is listed for both firefox and firefox-esr; either one keeps it
+        let known_owners: Vec<&String> = known_apps
+            .iter()
+            .filter(|(_, paths)| paths.contains(&path))
+            .map(|(pkg, _)| pkg)
+            .collect();
+        let installed = |pkg: &&String| owners.iter().any(|(_, names)| names.contains(&pkg.to_lowercase()));
+        if known_owners.iter().any(installed) {
+            continue;
+        }
+        let known_owner = known_owners.into_iter().min();
+
+        let mut reason = match known_owner {
+            Some(pkg) => format!("Known home path of {}, which is not installed; not owned by {}", pkg, checks),
+            None => format!("Not owned by {}", checks),
+        };
+        let mut confidence: u8 = 90;
+
+        let similar = owners
+            .iter()
+            .flat_map(|(_, names)| names)
+            .chain(&binaries)
+            .filter(|known| keys.iter().any(|key| resembles(key, known)))
+            .min();
+        if let Some(similar) = similar {
+            confidence -= 40;
+            reason.push_str(&format!("; resembles {}", similar));
+        }
+
+        match days_since_modified(&path) {
+            Some(days) if days < RECENT_DAYS => {
+                confidence -= 30;
+                reason.push_str(&format!("; modified {} days ago", days));
             }
-        } 
+            Some(days) => reason.push_str(&format!("; untouched for {} days", days)),
+            None => {}
+        }
+
+        artifacts.push(HomeArtifact {
+            path,
+            associated_package: known_owner.cloned(),
+            reason,
+            confidence,
+        });
+    }
+
+    // Runtime entries are only worth keeping while some process uses them
+    for path in stale_runtime_entries() {
+        if !artifacts.iter().any(|a| a.path == path) {
+            artifacts.push(HomeArtifact {
+                path,
+                associated_package: None,
+                reason: "Not in use by any running process".to_string(),
+                confidence: 80,
+            });
+        }
     }
 
     artifacts
 }
+
+/// Entries of `$XDG_RUNTIME_DIR` that no live process has open, mapped,
+/// bound as a socket or recorded in a pid file. If `/proc` cannot be read,
+/// nothing there can be shown to be unused and nothing is returned.
+///
+/// Mount points (and anything containing one) are never returned: FUSE
+/// daemons such as gvfsd-fuse or the document portal only hold `/dev/fuse`,
+/// and removing the entry would recurse into the mounted files.
+fn stale_runtime_entries() -> Vec<PathBuf> {
+    let Some(runtime_dir) = dirs::runtime_dir() else {
+        return Vec::new();
+    };
+    let Some(in_use) = paths_in_use() else {
+        return Vec::new();
+    };
+    let Ok(mountinfo) = std::fs::read_to_string("/proc/self/mountinfo") else {
+        return Vec::new();
+    };
+    let Ok(runtime_dev) = std::fs::metadata(&runtime_dir).map(|m| m.dev()) else {
+        return Vec::new();
+    };
+    let Ok(entries) = std::fs::read_dir(&runtime_dir) else {
+        return Vec::new();
+    };
+
+    let mount_points = parse_mount_points(&mountinfo);
+
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            std::fs::symlink_metadata(path).is_ok_and(|m| m.dev() == runtime_dev)
+                && !mount_points.iter().any(|mount| mount.starts_with(path))
+        })
+        .filter(|path| !in_use.iter().any(|used| used.starts_with(path)) && !has_live_pid_file(path))
+        .collect()
+}
+
+/// Mount point column of `/proc/self/mountinfo`, with the octal escapes
+/// (`\040` for a space) decoded.
+fn parse_mount_points(mountinfo: &str) -> Vec<PathBuf> {
+    mountinfo
+        .lines()
+        .filter_map(|line| line.split(' ').nth(4))
+        .map(|field| {
+            let mut decoded = Vec::new();
+            let mut bytes = field.bytes();
+            while let Some(b) = bytes.next() {
+                if b == b'\\' {
+                    let digits: Vec<u8> = bytes.by_ref().take(3).collect();
+                    let code = std::str::from_utf8(&digits).ok().and_then(|d| u8::from_str_radix(d, 8).ok());
+                    decoded.push(code.unwrap_or(b'?'));
+                } else {
+                    decoded.push(b);
+                }
+            }
+            PathBuf::from(std::ffi::OsString::from_vec(decoded))
+        })
+        .collect()
+}
+
+/// Paths any process we can inspect holds open, uses as its working
+/// directory or has mapped, plus every bound unix socket.
+fn paths_in_use() -> Option<HashSet<PathBuf>> {
+    let mut in_use = HashSet::new();
+
+    for entry in std::fs::read_dir("/proc").ok()?.flatten() {
+        if entry.file_name().to_string_lossy().parse::<u32>().is_err() {
+            continue;
+        }
+        let proc_dir = entry.path();
+
+        if let Ok(fds) = std::fs::read_dir(proc_dir.join("fd")) {
+            in_use.extend(fds.flatten().filter_map(|fd| std::fs::read_link(fd.path()).ok()));
+        }
+        if let Ok(cwd) = std::fs::read_link(proc_dir.join("cwd")) {
+            in_use.insert(cwd);
+        }
+        if let Ok(maps) = std::fs::read_to_string(proc_dir.join("maps")) {
+            in_use.extend(maps.lines().filter_map(|line| line.find('/').map(|i| PathBuf::from(&line[i..]))));
+        }
+    }
+
+    // Socket fds show up as `socket:[inode]`, so bound paths come from here
+    if let Ok(unix) = std::fs::read_to_string("/proc/net/unix") {
+        in_use.extend(parse_unix_sockets(&unix));
+    }
+
+    Some(in_use)
+}
+
+/// Paths column of `/proc/net/unix`; abstract and unbound sockets have none.
+fn parse_unix_sockets(table: &str) -> Vec<PathBuf> {
+    table
+        .lines()
+        .skip(1)
+        .filter_map(|line| line.split_whitespace().nth(7))
+        .filter(|path| path.starts_with('/'))
+        .map(PathBuf::from)
+        .collect()
+}
+
+/// A `*.pid` file naming a running process, either `path` itself or
+/// directly inside it.
+fn has_live_pid_file(path: &Path) -> bool {
+    let is_live = |pid_file: &Path| {
+        pid_file.extension().is_some_and(|ext| ext == "pid")
+            && std::fs::read_to_string(pid_file)
+                .ok()
+                .and_then(|pid| pid.trim().parse::<u32>().ok())
+                .is_some_and(|pid| Path::new("/proc").join(pid.to_string()).exists())
+    };
+
+    if is_live(path) {
+        return true;
+    }
+
+    std::fs::read_dir(path).is_ok_and(|entries| entries.flatten().any(|entry| is_live(&entry.path())))
+}
+
+/// Entries of every root plus the dot-entries of `home`, leaving out
+/// safelisted ones and those containing a root (`~/.config`, `~/.local`).
+fn scan_entries(roots: &[PathBuf], home: &Path) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+
+    for root in roots {
+        if let Ok(entries) = std::fs::read_dir(root) {
+            paths.extend(entries.flatten().map(|entry| entry.path()));
+        }
+    }
+
+    if let Ok(entries) = std::fs::read_dir(home) {
+        paths.extend(
+            entries
+                .flatten()
+                .filter(|entry| entry.file_name().to_string_lossy().starts_with('.'))
+                .map(|entry| entry.path())
+                .filter(|path| !is_safelisted(path, home) && !roots.iter().any(|root| root.starts_with(path))),
+        );
+    }
+
+    paths
+}
+
+fn is_safelisted(path: &Path, home: &Path) -> bool {
+    path.parent() == Some(home)
+        && path.file_name().and_then(|s| s.to_str()).is_some_and(|name| HOME_SAFELIST.contains(&name))
+}
+
+/// Names an entry could be owned under: `.nvim.log` -> `nvim.log` and `nvim`,
+/// `.nanorc` -> `nanorc` and `nano`; reverse-DNS names also by their last component.
+fn lookup_keys(entry: &str) -> Vec<String> {
+    let name = entry.trim_start_matches('.');
+    let mut keys = vec![name.to_string()];
+
+    if let Some((stem, _)) = name.split_once('.') {
+        keys.push(stem.to_string());
+    }
+    if let Some(app) = name.strip_suffix("rc")
+        && app.len() > 1
+        && !app.contains('.')
+    {
+        keys.push(app.to_string());
+    }
+    if name.split('.').count() >= 3
+        && let Some(last) = name.rsplit('.').next()
+    {
+        keys.push(last.to_string());
+    }
+
+    keys
+}
+
+/// Whether one name looks like a prefix of the other, e.g. `chromium` and
+/// `chromium-browser`. Short names match too much to count.
+fn resembles(key: &str, known: &str) -> bool {
+    key.len() >= 4 && known.len() >= 4 && (key.starts_with(known) || known.starts_with(key))
+}
+
+/// Lowercased names of the executables in every `$PATH` directory.
+fn path_binaries() -> HashSet<String> {
+    let Some(path) = std::env::var_os("PATH") else {
+        return HashSet::new();
+    };
+
+    std::env::split_paths(&path)
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.flatten())
+        .map(|entry| entry.file_name().to_string_lossy().to_lowercase())
+        .collect()
+}
+
+fn days_since_modified(path: &Path) -> Option<u64> {
+    let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    let age = SystemTime::now().duration_since(modified).ok()?;
+    Some(age.as_secs() / 86_400)
+}
+
+/// Lowercased names a package's home directories are likely to have,
+/// each with the reason it is expected: the package name itself, its
+/// binaries, its `.desktop` files and its per-application directories.
+pub fn expected_names(package: &str, files: &[PathBuf]) -> Vec<(String, String)> {
+    let mut names = vec![(package.to_lowercase(), "Matches removed package name".to_string())];
+
+    for file in files {
+        let Some(name) = owned_name(file) else {
+            continue;
+        };
+        let name = name.to_lowercase();
+
+        if name.len() > 1 && !names.iter().any(|(n, _)| *n == name) {
+            names.push((name, format!("Named after {} from {}", file.display(), package)));
+        }
+    }
+
+    names
+}
+
+/// The per-application directory (`/usr/share/<app>`, `/opt/<app>`, …) a
+/// path lies in, unless it is one of the [`SHARED_DIRS`].
+pub fn app_dir(file: &Path) -> Option<PathBuf> {
+    let (dir, app) = APP_DIRS.iter().find_map(|dir| {
+        let rest = file.strip_prefix(dir).ok()?;
+        Some((dir, rest.components().next()?.as_os_str().to_str()?))
+    })?;
+
+    // Multiarch directories like `/usr/lib/x86_64-linux-gnu`
+    if SHARED_DIRS.contains(&app) || app.contains("-linux-") {
+        return None;
+    }
+
+    Some(Path::new(dir).join(app))
+}
+
+/// The application name a single installed path points to, if any.
+fn owned_name(file: &Path) -> Option<String> {
+    let parent = file.parent()?.to_str()?;
+    let file_name = file.file_name()?.to_str()?;
+
+    if BIN_DIRS.contains(&parent) {
+        return Some(file_name.to_string());
+    }
+
+    // `org.xfce.mousepad.desktop` is the reverse-DNS form of `mousepad`
+    if parent == "/usr/share/applications" {
+        let stem = file_name.strip_suffix(".desktop")?;
+        return stem.rsplit('.').next().map(str::to_string);
+    }
+
+    let app_dir = app_dir(file)?;
+    Some(app_dir.file_name()?.to_str()?.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derives_names_from_binaries_desktop_files_and_app_dirs() {
+        let files: Vec<PathBuf> = [
+            "/usr/bin/code",
+            "/usr/share/applications/com.visualstudio.Code.desktop",
+            "/usr/share/code/resources/app",
+            "/usr/share/doc/vscode/copyright",
+            "/usr/lib/x86_64-linux-gnu/libfoo.so",
+            "/usr/lib/gtk-3.0/3.0.0/immodules/im-foo.so",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+
+        let names: Vec<_> = expected_names("vscode", &files).into_iter().map(|(name, _)| name).collect();
+
+        assert_eq!(names, ["vscode", "code"]);
+    }
+
+    #[test]
+    fn app_dir_is_the_first_component_below_an_app_root() {
+        assert_eq!(app_dir(Path::new("/usr/share/emacs/site-lisp/foo.el")), Some(PathBuf::from("/usr/share/emacs")));
+        assert_eq!(app_dir(Path::new("/opt/zoom/zoom")), Some(PathBuf::from("/opt/zoom")));
+        assert_eq!(app_dir(Path::new("/usr/lib/mozilla/plugins/libfoo.so")), None);
+        assert_eq!(app_dir(Path::new("/usr/bin/foo")), None);
+    }
+
+    #[test]
+    fn looks_entries_up_by_full_name_stem_and_app_id() {
+        assert_eq!(lookup_keys("nvim"), ["nvim"]);
+        assert_eq!(lookup_keys(".nvim.log"), ["nvim.log", "nvim"]);
+        assert_eq!(lookup_keys("org.gnome.epiphany"), ["org.gnome.epiphany", "org", "epiphany"]);
+        assert_eq!(lookup_keys(".nanorc"), ["nanorc", "nano"]);
+    }
+
+    #[test]
+    fn reads_bound_socket_paths() {
+        let table = "\
+Num       RefCount Protocol Flags    Type St Inode Path
+0000000000000000: 00000002 00000000 00010000 0001 01 21503 /run/user/1000/bus
+0000000000000000: 00000002 00000000 00010000 0001 01 24117 @/tmp/.X11-unix/X0
+0000000000000000: 00000003 00000000 00000000 0001 03 24830
+";
+
+        assert_eq!(parse_unix_sockets(table), [PathBuf::from("/run/user/1000/bus")]);
+    }
+
+    #[test]
+    fn reads_escaped_mount_points() {
+        let mountinfo = "\
+22 1 259:2 / / rw,relatime shared:1 - ext4 /dev/nvme0n1p2 rw
+45 30 0:40 / /run/user/1000 rw,nosuid,nodev shared:24 - tmpfs tmpfs rw,mode=700
+47 45 0:42 / /run/user/1000/gvfs rw,nosuid,nodev shared:26 - fuse.gvfsd-fuse gvfsd-fuse rw
+48 45 0:43 / /run/user/1000/My\\040Share rw shared:27 - fuse.sshfs host: rw
+";
+
+        assert_eq!(
+            parse_mount_points(mountinfo),
+            [
+                PathBuf::from("/"),
+                PathBuf::from("/run/user/1000"),
+                PathBuf::from("/run/user/1000/gvfs"),
+                PathBuf::from("/run/user/1000/My Share"),
+            ]
+        );
+    }
+
+    #[test]
+    fn safelist_only_applies_directly_in_home() {
+        let home = Path::new("/home/user");
+
+        assert!(is_safelisted(&home.join(".ssh"), home));
+        assert!(is_safelisted(&home.join(".bashrc"), home));
+        assert!(!is_safelisted(&home.join(".mozilla"), home));
+        assert!(!is_safelisted(&home.join(".config/.ssh"), home));
+    }
+}
@@ -0,0 +1,78 @@
+use crate::types::compare_versions;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Release of the running kernel, as `uname -r` reports it. Empty for any
+/// `root` but `/`: no kernel of an inspected system is running.
+pub fn running_release(root: &Path) -> anyhow::Result<String> {
+    if root != Path::new("/") {
+        return Ok(String::new());
+    }
+
+    let output = Command::new("uname")
+        .args(["-r"])
+        .output()?;
+
+    if !output.status.success() {
+        anyhow::bail!("uname -r failed");
+    }
+
+    Ok(String::from_utf8(output.stdout)?.trim().to_string())
+}
+
+/// Picks the kernels that are neither running nor among the `keep` newest.
+pub fn select_removable(mut installed: Vec<String>, running: &str, keep: usize) -> Vec<String> {
+    installed.sort_by(|a, b| compare_versions(b, a));
+    installed.dedup();
+
+    installed
+        .into_iter()
+        .skip(keep)
+        .filter(|release| release != running)
+        .collect()
+}
+
+/// Module directory and `/boot` images that belong to `release`.
+pub fn release_files(root: &Path, release: &str) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+
+    let modules = root.join("lib/modules").join(release);
+    if modules.exists() {
+        files.push(modules);
+    }
+
+    if let Ok(entries) = std::fs::read_dir(root.join("boot")) {
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+
+            // vmlinuz-<r>, initrd.img-<r>, System.map-<r>, config-<r>, initramfs-<r>.img
+            if name.ends_with(&format!("-{}", release)) || name.ends_with(&format!("-{}.img", release)) {
+                files.push(entry.path());
+            }
+        }
+    }
+
+    files
+}
+
+/// Total size of `paths`, descending into directories.
+pub fn disk_usage(paths: &[PathBuf]) -> u64 {
+    paths
+        .iter()
+        .map(|path| {
+            let Ok(meta) = std::fs::symlink_metadata(path) else {
+                return 0;
+            };
+
+            if meta.is_dir() {
+                let children: Vec<PathBuf> = std::fs::read_dir(path)
+                    .map(|entries| entries.flatten().map(|e| e.path()).collect())
+                    .unwrap_or_default();
+                disk_usage(&children)
+            } else {
+                meta.len()
+            }
+        })
+        .sum()
+}
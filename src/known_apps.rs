@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Home paths well-known applications create, keyed by the package names
+/// distributions ship them under. `~` is the user's home directory.
+const KNOWN_APPS: &[(&str, &[&str])] = &[
+    ("firefox", &["~/.mozilla", "~/.cache/mozilla"]),
+    ("firefox-esr", &["~/.mozilla", "~/.cache/mozilla"]),
+    ("thunderbird", &["~/.thunderbird", "~/.cache/thunderbird"]),
+    ("chromium", &["~/.config/chromium", "~/.cache/chromium"]),
+    ("google-chrome-stable", &["~/.config/google-chrome", "~/.cache/google-chrome"]),
+    ("brave-browser", &["~/.config/BraveSoftware", "~/.cache/BraveSoftware"]),
+    ("code", &["~/.config/Code", "~/.vscode"]),
+    ("vscode", &["~/.config/Code", "~/.vscode"]),
+    ("visual-studio-code-bin", &["~/.config/Code", "~/.vscode"]),
+    ("codium", &["~/.config/VSCodium", "~/.vscode-oss"]),
+    ("sublime-text", &["~/.config/sublime-text", "~/.cache/sublime-text"]),
+    ("neovim", &["~/.config/nvim", "~/.local/share/nvim", "~/.local/state/nvim", "~/.cache/nvim"]),
+    ("vim", &["~/.vim", "~/.viminfo"]),
+    ("emacs", &["~/.emacs.d", "~/.emacs"]),
+    ("libreoffice", &["~/.config/libreoffice"]),
+    ("gimp", &["~/.config/GIMP", "~/.cache/gimp"]),
+    ("inkscape", &["~/.config/inkscape"]),
+    ("blender", &["~/.config/blender", "~/.cache/blender"]),
+    ("vlc", &["~/.config/vlc", "~/.local/share/vlc", "~/.cache/vlc"]),
+    ("mpv", &["~/.config/mpv", "~/.local/state/mpv"]),
+    ("steam", &["~/.steam", "~/.local/share/Steam"]),
+    ("discord", &["~/.config/discord"]),
+    ("telegram-desktop", &["~/.local/share/TelegramDesktop"]),
+    ("signal-desktop", &["~/.config/Signal"]),
+    ("slack-desktop", &["~/.config/Slack"]),
+    ("zoom", &["~/.zoom", "~/.config/zoomus.conf"]),
+    ("spotify", &["~/.config/spotify", "~/.cache/spotify"]),
+    ("obs-studio", &["~/.config/obs-studio"]),
+    ("docker", &["~/.docker"]),
+    ("wine", &["~/.wine"]),
+    ("rustup", &["~/.rustup", "~/.cargo"]),
+    ("nodejs", &["~/.npm", "~/.node_repl_history"]),
+    ("python3-pip", &["~/.cache/pip"]),
+    ("python-pip", &["~/.cache/pip"]),
+    ("gradle", &["~/.gradle"]),
+    ("maven", &["~/.m2"]),
+    ("virtualbox", &["~/.config/VirtualBox"]),
+];
+
+/// Built-in entries merged with the `[known_apps]` table from the config,
+/// whose paths are added to (not replacing) the built-in ones.
+pub fn home_paths(extra: &HashMap<String, Vec<String>>) -> HashMap<String, Vec<PathBuf>> {
+    let home = dirs::home_dir().expect("no home dir");
+    let expand = |path: &str| match path.strip_prefix("~/") {
+        Some(rest) => home.join(rest),
+        None => PathBuf::from(path),
+    };
+
+    let mut known: HashMap<String, Vec<PathBuf>> = KNOWN_APPS
+        .iter()
+        .map(|(pkg, paths)| (pkg.to_string(), paths.iter().map(|p| expand(p)).collect()))
+        .collect();
+
+    for (pkg, paths) in extra {
+        let entry = known.entry(pkg.clone()).or_default();
+        for path in paths.iter().map(|p| expand(p)) {
+            if !entry.contains(&path) {
+                entry.push(path);
+            }
+        }
+    }
+
+    known
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn configured_paths_extend_builtin_ones() {
+        let home = dirs::home_dir().unwrap();
+        let extra = HashMap::from([
+            ("firefox".to_string(), vec!["~/.mozilla".to_string(), "~/.firefox-extra".to_string()]),
+            ("myapp".to_string(), vec!["/opt/myapp-data".to_string()]),
+        ]);
+
+        let known = home_paths(&extra);
+
+        assert_eq!(
+            known["firefox"],
+            [home.join(".mozilla"), home.join(".cache/mozilla"), home.join(".firefox-extra")]
+        );
+        assert_eq!(known["myapp"], [PathBuf::from("/opt/myapp-data")]);
+    }
+}
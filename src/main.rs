@@ -1,12 +1,27 @@
 mod types;
+mod backend;
 mod xbps;
 mod dpkg;
+mod pacman;
+mod rpm;
+mod apk;
+mod zypper;
+mod portage;
+mod flatpak;
+mod snap;
+mod nix;
+mod kernel;
+mod depgraph;
 mod home_scanner;
+mod known_apps;
 mod tui;
 mod config;
 
 use clap::Parser;
-use types::{SweepItem, OS};
+use std::path::PathBuf;
+use backend::{ScanScope, Scanner};
+use std::collections::{HashMap, HashSet};
+use types::{PackageSystem, SweepItem};
 use config::Config;
 
 #[derive(Parser)]
@@ -22,58 +37,175 @@ struct Cli {
     /// Perform real deletion (requires confirmation).
     #[arg(long)]
     delete: bool,
+
+    /// After removing packages, re-scan and offer whatever they left orphaned.
+    #[arg(long)]
+    cascade: bool,
+
+    /// Also offer home directory entries that no installed package, flatpak or `$PATH` binary owns.
+    #[arg(long)]
+    deep_scan: bool,
+
+    /// Inspect the system mounted at this path instead of `/` (dry-run only).
+    #[arg(long, value_name = "PATH")]
+    root: Option<PathBuf>,
 }
 
 fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
-    let config = Config::load_config()?;
+    if cli.delete && cli.root.is_some() {
+        anyhow::bail!("--delete cannot be combined with --root");
+    }
+    if cli.deep_scan && cli.root.is_some() {
+        anyhow::bail!("--deep-scan checks your own home directory and cannot be combined with --root");
+    }
 
-    let mut sweep_items = Vec::new();
-    let mut package_names = Vec::new();
+    let config = Config::load_config()?;
 
-    let current_os = match config.os {
-        Some(ref os) => os,
-        None => &detect_os(),
+    let scope = ScanScope {
+        orphans: cli.orphans || !cli.residual,
+        residual: cli.residual || !cli.orphans,
+        root: cli.root.clone().unwrap_or_else(|| PathBuf::from("/")),
+        keep_kernels: config.keep_kernels,
+        keep_cached: config.keep_cached,
+        protected: config.protected_packages.clone(),
     };
 
-    match current_os {
-        OS::Void => {
-            if cli.orphans || (!cli.orphans && !cli.residual) {
-                for pkg in xbps::list_orphans()? {
-                    package_names.push(pkg.name.clone());
-                    sweep_items.push(SweepItem::Package(pkg));
-                }
-            }
-        },
-        OS::Debian => {
-            if cli.residual || (!cli.orphans && !cli.residual) {
-                for pkg in dpkg::list_residual_configs()? {
-                    package_names.push(pkg.name.clone());
-                    sweep_items.push(SweepItem::Package(pkg));
-                }
-            }
-        },
-        OS::Unsupported => anyhow::bail!("Unsupported system"),
+    let scanners = backend::enabled(&config, &scope.root);
+    if scanners.is_empty() {
+        anyhow::bail!("Unsupported system");
     }
 
-    let home_artifacts = home_scanner::find_suspicious_artifacts(&package_names);
-    for artifact in home_artifacts {
-        sweep_items.push(SweepItem::HomeArtifact(artifact));
-    }
+    let known_apps = known_apps::home_paths(&config.known_apps);
 
+    let mut sweep_items = collect_items(&scanners, &scope, cli.deep_scan, &known_apps);
     if sweep_items.is_empty() {
         println!("✅ Nothing to clean!");
         return Ok(());
     }
 
-    let mut app = tui::App::new(sweep_items, !cli.delete, config);
-    app.run()?;
-    
+    // Items are identified by how they are displayed; anything offered once
+    // is never offered again, whether it was removed or declined.
+    let mut offered: HashSet<String> = HashSet::new();
+
+    loop {
+        offered.extend(sweep_items.iter().map(|item| item.to_string()));
+
+        let mut app = tui::App::new(sweep_items, !cli.delete, config.clone(), scope.clone());
+        let removed_packages = app.run()?;
+
+        if !cli.cascade || !removed_packages {
+            break;
+        }
+
+        sweep_items = collect_items(&scanners, &scope, cli.deep_scan, &known_apps)
+            .into_iter()
+            .filter(|item| !offered.contains(&item.to_string()))
+            .collect();
+
+        if sweep_items.is_empty() {
+            println!("✅ Nothing new was orphaned.");
+            break;
+        }
+
+        println!("\n🔁 Found {} newly orphaned items, press Enter to review them...", sweep_items.len());
+        crossterm::event::read()?;
+    }
+
     Ok(())
 }
 
-fn detect_os() -> OS {
-    if std::path::Path::new("/usr/bin/xbps-query").exists() { return OS::Void; }
-    if std::path::Path::new("/usr/bin/dpkg").exists() { return OS::Debian; }
-    OS::Unsupported
+/// Runs every scanner and adds home artifacts matching the packages found,
+/// plus, for a deep scan, home entries nothing installed owns.
+fn collect_items(
+    scanners: &[Box<dyn Scanner>],
+    scope: &ScanScope,
+    deep_scan: bool,
+    known_apps: &HashMap<String, Vec<PathBuf>>,
+) -> Vec<SweepItem> {
+    let mut sweep_items = Vec::new();
+    let mut footprints = Vec::new();
+    let mut dir_owners: Vec<(PackageSystem, HashMap<PathBuf, HashSet<String>>)> = Vec::new();
+
+    for scanner in scanners {
+        // Listings that come from the running system's tools describe the host, not `--root`
+        if !scope.is_host() && !scanner.supports_root() {
+            continue;
+        }
+
+        // One broken backend on a hybrid system should not hide the others
+        let items = match scanner.scan(scope) {
+            Ok(items) => items,
+            Err(e) => {
+                eprintln!("⚠️  {} scan failed: {}", scanner.name(), e);
+                continue;
+            },
+        };
+
+        for item in items {
+            // Protected packages are never offered, so neither is what they left in home
+            if let SweepItem::Package(ref pkg) = item
+                && !pkg.protected
+            {
+                // Without a file list the package name alone is still matched
+                let backend = backend::for_system(&pkg.system);
+                let mut files = backend.package_files(&scope.root, &pkg.name).unwrap_or_default();
+
+                if !files.is_empty() {
+                    let index = dir_owners.iter().position(|(system, _)| *system == pkg.system).unwrap_or_else(|| {
+                        dir_owners.push((pkg.system.clone(), backend::app_dir_owners(backend.as_ref(), scope)));
+                        dir_owners.len() - 1
+                    });
+                    let owners = &dir_owners[index].1;
+
+                    // `/usr/share/emacs` from an `elpa-*` addon says nothing about `~/.emacs.d`
+                    files.retain(|file| {
+                        home_scanner::app_dir(file)
+                            .and_then(|dir| owners.get(&dir))
+                            .is_none_or(|pkgs| pkgs.iter().all(|owner| *owner == pkg.name))
+                    });
+                }
+
+                footprints.push(home_scanner::PackageFootprint { name: pkg.name.clone(), files });
+            }
+            sweep_items.push(item);
+        }
+    }
+
+    // The home directory scanned is the invoking user's on the host, not one under `--root`
+    if !scope.is_host() {
+        return sweep_items;
+    }
+
+    let installed: HashSet<String> = backend::all()
+        .into_iter()
+        .filter(|b| !footprints.is_empty() && scanners.iter().any(|s| s.name() == b.name()))
+        .flat_map(|b| b.installed_packages(scope).unwrap_or_default())
+        .map(|name| name.to_lowercase())
+        .collect();
+    let mut home_artifacts = home_scanner::find_suspicious_artifacts(&footprints, known_apps, &installed);
+
+    if deep_scan {
+        let mut owners = Vec::new();
+        for scanner in scanners {
+            match scanner.installed_names(scope) {
+                // Scanners that cannot list what they installed did not check anything
+                Ok(names) if names.is_empty() => {}
+                Ok(names) => owners.push((scanner.name(), names)),
+                Err(e) => eprintln!("⚠️  {} could not list installed packages: {}", scanner.name(), e),
+            }
+        }
+
+        for artifact in home_scanner::find_unowned_artifacts(&owners, known_apps) {
+            if !home_artifacts.iter().any(|a| a.path == artifact.path) {
+                home_artifacts.push(artifact);
+            }
+        }
+    }
+
+    for artifact in home_artifacts {
+        sweep_items.push(SweepItem::HomeArtifact(artifact));
+    }
+
+    sweep_items
 }
@@ -0,0 +1,120 @@
+use crate::backend::{ScanScope, Scanner};
+use crate::types::{NixLeftover, SweepItem};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const SYSTEM_PROFILE: &str = "/nix/var/nix/profiles/system";
+
+pub struct Nix;
+
+impl Scanner for Nix {
+    fn name(&self) -> &'static str {
+        "nix"
+    }
+
+    /// Nix can live next to any distro's package manager, so this checks
+    /// for the store rather than for a particular OS.
+    fn detect(&self, _root: &Path) -> bool {
+        Path::new("/nix/store").exists() && Path::new("/nix/var/nix/profiles").exists()
+    }
+
+    fn supports_root(&self) -> bool {
+        false
+    }
+
+    fn scan(&self, scope: &ScanScope) -> anyhow::Result<Vec<SweepItem>> {
+        if !scope.orphans {
+            return Ok(Vec::new());
+        }
+
+        Ok(list_leftovers()?.into_iter().map(SweepItem::Nix).collect())
+    }
+}
+
+/// Non-current generations of the user profile and, on NixOS, of the
+/// system profile, followed by what a garbage collection would free.
+pub fn list_leftovers() -> anyhow::Result<Vec<NixLeftover>> {
+    let mut leftovers = list_generations(None)?;
+
+    if Path::new(SYSTEM_PROFILE).exists() {
+        leftovers.extend(list_generations(Some(PathBuf::from(SYSTEM_PROFILE)))?);
+    }
+
+    if let Some(garbage) = garbage_summary()? {
+        leftovers.push(garbage);
+    }
+
+    Ok(leftovers)
+}
+
+/// `profile` of `None` means the invoking user's default profile.
+fn list_generations(profile: Option<PathBuf>) -> anyhow::Result<Vec<NixLeftover>> {
+    let mut command = Command::new("nix-env");
+    command.arg("--list-generations");
+    if let Some(ref profile) = profile {
+        command.arg("-p").arg(profile);
+    }
+
+    let output = command.output()?;
+    if !output.status.success() {
+        anyhow::bail!("nix-env --list-generations failed");
+    }
+
+    let stdout = String::from_utf8(output.stdout)?;
+    let mut generations = Vec::new();
+
+    // `   41   2024-01-30 09:12:44   ` / `   42   2024-02-01 10:11:12   (current)`
+    for line in stdout.lines() {
+        let cols: Vec<&str> = line.split_whitespace().collect();
+        if cols.last() == Some(&"(current)") {
+            continue;
+        }
+
+        let (Some(id), Some(date), Some(time)) = (cols.first(), cols.get(1), cols.get(2)) else {
+            continue;
+        };
+        let Ok(id) = id.parse::<u32>() else {
+            continue;
+        };
+
+        generations.push(NixLeftover::Generation {
+            profile: profile.clone(),
+            id,
+            created: format!("{} {}", date, time),
+        });
+    }
+
+    Ok(generations)
+}
+
+/// Summarises the store paths `nix-collect-garbage --dry-run` would delete.
+fn garbage_summary() -> anyhow::Result<Option<NixLeftover>> {
+    let output = Command::new("nix-store")
+        .args(["--gc", "--print-dead"])
+        .output()?;
+
+    if !output.status.success() {
+        anyhow::bail!("nix-store --gc --print-dead failed");
+    }
+
+    let stdout = String::from_utf8(output.stdout)?;
+    let dead: Vec<&str> = stdout.lines().filter(|l| l.starts_with("/nix/store/")).collect();
+    if dead.is_empty() {
+        return Ok(None);
+    }
+
+    let mut size = 0;
+    for chunk in dead.chunks(512) {
+        let output = Command::new("nix-store")
+            .args(["--query", "--size"])
+            .args(chunk)
+            .output()?;
+
+        size += String::from_utf8(output.stdout)?
+            .lines()
+            .filter_map(|l| l.trim().parse::<u64>().ok())
+            .sum::<u64>();
+    }
+
+    Ok(Some(NixLeftover::Garbage { paths: dead.len(), size }))
+}
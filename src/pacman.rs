@@ -0,0 +1,109 @@
+use crate::backend::{self, PackageBackend, ScanScope};
+use crate::types::{ConfigLeftover, Package, PackageSystem, OS};
+use std::path::Path;
+use std::process::Command;
+
+pub struct Pacman;
+
+impl PackageBackend for Pacman {
+    fn name(&self) -> &'static str {
+        "pacman"
+    }
+
+    fn os(&self) -> OS {
+        OS::Arch
+    }
+
+    fn system(&self) -> PackageSystem {
+        PackageSystem::Pacman
+    }
+
+    fn detect(&self, root: &Path) -> bool {
+        root.join("usr/bin/pacman").exists()
+    }
+
+    fn supports_root(&self) -> bool {
+        false
+    }
+
+    fn list_candidates(&self, scope: &ScanScope) -> anyhow::Result<Vec<Package>> {
+        if scope.orphans {
+            list_orphans()
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    fn installed_packages(&self, _scope: &ScanScope) -> anyhow::Result<Vec<String>> {
+        let output = Command::new("pacman").arg("-Qq").output()?;
+
+        if !output.status.success() {
+            anyhow::bail!("pacman -Qq failed");
+        }
+
+        Ok(String::from_utf8(output.stdout)?.lines().map(|l| l.trim().to_string()).collect())
+    }
+
+    fn list_leftovers(&self, scope: &ScanScope) -> anyhow::Result<Vec<ConfigLeftover>> {
+        let suffixes = [(".pacnew", backend::UNMERGED_CONFIG), (".pacsave", backend::SAVED_CONFIG)];
+        Ok(backend::find_config_leftovers(&scope.root, &suffixes))
+    }
+
+    fn removal_command(&self, pkgs: &[&Package]) -> Vec<String> {
+        let mut cmd = vec!["pacman".to_string(), "-Rns".to_string(), "--noconfirm".to_string()];
+        cmd.extend(pkgs.iter().map(|p| p.name.clone()));
+        cmd
+    }
+
+    fn simulate_removal(&self, pkgs: &[&Package]) -> anyhow::Result<Option<Vec<String>>> {
+        let output = Command::new("pacman")
+            .args(["-Rns", "--print", "--print-format", "%n"])
+            .args(pkgs.iter().map(|p| &p.name))
+            .output()?;
+
+        if !output.status.success() {
+            anyhow::bail!("pacman -Rns --print failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+
+        let names = String::from_utf8(output.stdout)?
+            .lines()
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty())
+            .collect();
+
+        Ok(Some(names))
+    }
+}
+
+pub fn list_orphans() -> anyhow::Result<Vec<Package>> {
+    let output = Command::new("pacman")
+        .args(["-Qdt"])
+        .output()?;
+
+    // pacman exits with 1 and prints nothing when there are no orphans
+    if !output.status.success() && !output.stderr.is_empty() {
+        anyhow::bail!("pacman -Qdt failed");
+    }
+
+    let stdout = String::from_utf8(output.stdout)?;
+    let mut packages = Vec::new();
+
+    for line in stdout.lines() {
+        let mut parts = line.split_whitespace();
+        if let Some(name) = parts.next() {
+            packages.push(Package {
+                name: name.to_string(),
+                version: parts.next().unwrap_or("unknown").to_string(),
+                description: "Orphaned package".to_string(),
+                installed: true,
+                installed_size: None,
+                system: PackageSystem::Pacman,
+                explanation: None,
+                protected: false,
+                category: None,
+            });
+        }
+    }
+
+    Ok(packages)
+}
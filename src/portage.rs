@@ -0,0 +1,206 @@
+use crate::backend::{PackageBackend, ScanScope};
+use crate::types::{CacheFile, Package, PackageSystem, OS};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const VDB_PATH: &str = "var/db/pkg";
+
+pub struct Portage;
+
+impl PackageBackend for Portage {
+    fn name(&self) -> &'static str {
+        "portage"
+    }
+
+    fn os(&self) -> OS {
+        OS::Gentoo
+    }
+
+    fn system(&self) -> PackageSystem {
+        PackageSystem::Portage
+    }
+
+    fn detect(&self, root: &Path) -> bool {
+        root.join("usr/bin/emerge").exists()
+    }
+
+    fn supports_root(&self) -> bool {
+        false
+    }
+
+    fn list_candidates(&self, scope: &ScanScope) -> anyhow::Result<Vec<Package>> {
+        if scope.orphans {
+            list_depclean(&scope.root)
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    /// Package names without category or version, read from the installed
+    /// package database (`var/db/pkg/<category>/<name>-<version>`).
+    fn installed_packages(&self, scope: &ScanScope) -> anyhow::Result<Vec<String>> {
+        let mut names = Vec::new();
+
+        for category in std::fs::read_dir(scope.root.join(VDB_PATH))?.flatten() {
+            let Ok(entries) = std::fs::read_dir(category.path()) else {
+                continue;
+            };
+
+            for entry in entries.flatten() {
+                let dir_name = entry.file_name().to_string_lossy().into_owned();
+                names.push(strip_version(&dir_name).to_string());
+            }
+        }
+
+        Ok(names)
+    }
+
+    fn list_caches(&self, _scope: &ScanScope) -> anyhow::Result<Vec<CacheFile>> {
+        let Ok(output) = Command::new("portageq").args(["envvar", "DISTDIR", "PKGDIR"]).output() else {
+            return Ok(Vec::new());
+        };
+
+        let stdout = String::from_utf8(output.stdout)?;
+        let mut dirs = stdout.lines().map(PathBuf::from);
+        let distdir = dirs.next().unwrap_or_else(|| PathBuf::from("/var/cache/distfiles"));
+        let pkgdir = dirs.next().unwrap_or_else(|| PathBuf::from("/var/cache/binpkgs"));
+
+        let mut caches = list_eclean("distfiles", &distdir, "Distfile not needed by any installed package")?;
+        caches.extend(list_eclean("packages", &pkgdir, "Binary package not matching any installed package")?);
+
+        Ok(caches)
+    }
+
+    fn removal_command(&self, pkgs: &[&Package]) -> Vec<String> {
+        let mut cmd = vec!["emerge".to_string(), "--depclean".to_string()];
+        cmd.extend(pkgs.iter().map(|p| match &p.category {
+            Some(category) => format!("={}/{}-{}", category, p.name, p.version),
+            None => format!("={}-{}", p.name, p.version),
+        }));
+        cmd
+    }
+}
+
+/// Every version `emerge --pretend --depclean` would unmerge, with slot and
+/// description taken from the installed package database.
+pub fn list_depclean(root: &Path) -> anyhow::Result<Vec<Package>> {
+    let output = Command::new("emerge")
+        .args(["--pretend", "--depclean", "--color=n"])
+        .output()?;
+
+    if !output.status.success() {
+        anyhow::bail!("emerge --depclean failed");
+    }
+
+    let stdout = String::from_utf8(output.stdout)?;
+    let packages = parse_depclean(&stdout)
+        .into_iter()
+        .map(|(atom, version)| {
+            let vdb_dir = root.join(VDB_PATH).join(format!("{}-{}", atom, version));
+            let read = |file: &str| {
+                std::fs::read_to_string(vdb_dir.join(file))
+                    .map(|s| s.trim().to_string())
+                    .unwrap_or_default()
+            };
+
+            let description = match read("SLOT").as_str() {
+                "" => read("DESCRIPTION"),
+                slot => format!("{} (slot {})", read("DESCRIPTION"), slot),
+            };
+
+            // Names match `installed_packages` and `protected_packages` without the category
+            let (category, name) = match atom.split_once('/') {
+                Some((category, name)) => (Some(category.to_string()), name.to_string()),
+                None => (None, atom),
+            };
+
+            Package {
+                name,
+                version,
+                description,
+                installed: true,
+                installed_size: read("SIZE").parse().ok(),
+                system: PackageSystem::Portage,
+                explanation: None,
+                protected: false,
+                category,
+            }
+        })
+        .collect();
+
+    Ok(packages)
+}
+
+/// `gentoo-sources-6.1.12` -> `gentoo-sources`: the version starts at the
+/// first dash followed by a digit.
+fn strip_version(name_version: &str) -> &str {
+    name_version
+        .match_indices('-')
+        .find(|(i, _)| name_version[i + 1..].starts_with(|c: char| c.is_ascii_digit()))
+        .map_or(name_version, |(i, _)| &name_version[..i])
+}
+
+/// Parses the depclean summary into `(category/package, version)` pairs:
+///
+/// ```text
+///  sys-kernel/gentoo-sources
+///     selected: 6.1.12 6.1.19
+///    protected: none
+///      omitted: 6.1.31
+/// ```
+fn parse_depclean(output: &str) -> Vec<(String, String)> {
+    let mut selected = Vec::new();
+    let mut current: Option<&str> = None;
+
+    for line in output.lines() {
+        let trimmed = line.trim();
+
+        if let Some(versions) = trimmed.strip_prefix("selected:") {
+            let Some(atom) = current else {
+                continue;
+            };
+
+            for version in versions.split_whitespace().filter(|v| *v != "none") {
+                selected.push((atom.to_string(), version.to_string()));
+            }
+        } else if line.starts_with(' ') && trimmed.contains('/') && !trimmed.contains([' ', ':']) {
+            current = Some(trimmed);
+        }
+    }
+
+    selected
+}
+
+/// Files `eclean --pretend <target>` would delete from `dir`.
+fn list_eclean(target: &str, dir: &Path, reason: &str) -> anyhow::Result<Vec<CacheFile>> {
+    // eclean ships with gentoolkit, which is not always installed
+    let Ok(output) = Command::new("eclean").args(["--pretend", target]).output() else {
+        return Ok(Vec::new());
+    };
+
+    let stdout = String::from_utf8(output.stdout)?;
+    let files = parse_eclean(&stdout)
+        .into_iter()
+        .map(|name| {
+            let path = dir.join(name);
+            CacheFile {
+                size: std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0),
+                path,
+                related: Vec::new(),
+                reason: reason.to_string(),
+            }
+        })
+        .collect();
+
+    Ok(files)
+}
+
+/// Picks file names out of eclean's ` [   1.2 M ] foo-1.2.tar.gz` lines.
+fn parse_eclean(output: &str) -> Vec<&str> {
+    output
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix('[')?.split_once(']'))
+        .map(|(_, name)| name.trim())
+        .filter(|name| !name.is_empty() && !name.starts_with("Total space"))
+        .collect()
+}
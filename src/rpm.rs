@@ -0,0 +1,100 @@
+use crate::backend::{self, PackageBackend, ScanScope};
+use crate::types::{ConfigLeftover, Package, PackageSystem, OS};
+use std::path::Path;
+use std::process::Command;
+
+pub struct Rpm;
+
+impl PackageBackend for Rpm {
+    fn name(&self) -> &'static str {
+        "rpm"
+    }
+
+    fn os(&self) -> OS {
+        OS::Fedora
+    }
+
+    fn system(&self) -> PackageSystem {
+        PackageSystem::Rpm
+    }
+
+    fn detect(&self, root: &Path) -> bool {
+        root.join("usr/bin/dnf").exists()
+    }
+
+    fn supports_root(&self) -> bool {
+        false
+    }
+
+    fn list_candidates(&self, scope: &ScanScope) -> anyhow::Result<Vec<Package>> {
+        if scope.orphans {
+            list_unneeded()
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    fn installed_packages(&self, _scope: &ScanScope) -> anyhow::Result<Vec<String>> {
+        list_installed()
+    }
+
+    fn list_leftovers(&self, scope: &ScanScope) -> anyhow::Result<Vec<ConfigLeftover>> {
+        let suffixes = [(".rpmnew", backend::UNMERGED_CONFIG), (".rpmsave", backend::SAVED_CONFIG)];
+        Ok(backend::find_config_leftovers(&scope.root, &suffixes))
+    }
+
+    fn removal_command(&self, pkgs: &[&Package]) -> Vec<String> {
+        let mut cmd = vec!["dnf".to_string(), "remove".to_string(), "-y".to_string()];
+        cmd.extend(pkgs.iter().map(|p| p.name.clone()));
+        cmd
+    }
+}
+
+/// Names of every package in the rpm database. Shared with zypper.
+pub fn list_installed() -> anyhow::Result<Vec<String>> {
+    let output = Command::new("rpm")
+        .args(["-qa", "--queryformat", "%{name}\\n"])
+        .output()?;
+
+    if !output.status.success() {
+        anyhow::bail!("rpm -qa failed");
+    }
+
+    Ok(String::from_utf8(output.stdout)?.lines().map(|l| l.trim().to_string()).collect())
+}
+
+/// Packages `dnf autoremove` would take out: installed as dependencies
+/// that nothing requires anymore.
+pub fn list_unneeded() -> anyhow::Result<Vec<Package>> {
+    let output = Command::new("dnf")
+        .args(["repoquery", "--unneeded", "--queryformat", "%{name}\\t%{evr}\\t%{summary}\\n"])
+        .output()?;
+
+    if !output.status.success() {
+        anyhow::bail!("dnf repoquery failed");
+    }
+
+    let stdout = String::from_utf8(output.stdout)?;
+    let mut packages = Vec::new();
+
+    for line in stdout.lines() {
+        let mut fields = line.split('\t');
+        let (Some(name), Some(version)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+
+        packages.push(Package {
+            name: name.to_string(),
+            version: version.to_string(),
+            description: fields.next().unwrap_or("Unneeded package").to_string(),
+            installed: true,
+            installed_size: None,
+            system: PackageSystem::Rpm,
+            explanation: None,
+            protected: false,
+            category: None,
+        });
+    }
+
+    Ok(packages)
+}
@@ -0,0 +1,121 @@
+use crate::backend::{ScanScope, Scanner};
+use crate::types::{SnapLeftover, SweepItem};
+use std::collections::HashSet;
+use std::process::Command;
+
+const SNAPS_DIR: &str = "/var/lib/snapd/snaps";
+
+pub struct Snap;
+
+impl Scanner for Snap {
+    fn name(&self) -> &'static str {
+        "snap"
+    }
+
+    fn detect(&self, _root: &std::path::Path) -> bool {
+        std::path::Path::new("/usr/bin/snap").exists()
+    }
+
+    fn supports_root(&self) -> bool {
+        false
+    }
+
+    fn scan(&self, scope: &ScanScope) -> anyhow::Result<Vec<SweepItem>> {
+        if !scope.orphans {
+            return Ok(Vec::new());
+        }
+
+        Ok(list_leftovers()?.into_iter().map(SweepItem::Snap).collect())
+    }
+
+    fn installed_names(&self, _scope: &ScanScope) -> anyhow::Result<HashSet<String>> {
+        let output = Command::new("snap").arg("list").output()?;
+
+        if !output.status.success() {
+            anyhow::bail!("snap list failed");
+        }
+
+        let stdout = String::from_utf8(output.stdout)?;
+        Ok(parse_snap_list(&stdout).into_iter().map(|r| r.name.to_lowercase()).collect())
+    }
+}
+
+/// Disabled revisions kept around after refreshes plus `~/snap/<name>`
+/// directories of snaps that are no longer installed.
+pub fn list_leftovers() -> anyhow::Result<Vec<SnapLeftover>> {
+    let output = Command::new("snap")
+        .args(["list", "--all"])
+        .output()?;
+
+    if !output.status.success() {
+        anyhow::bail!("snap list failed");
+    }
+
+    let stdout = String::from_utf8(output.stdout)?;
+    let rows = parse_snap_list(&stdout);
+    let installed: HashSet<&str> = rows.iter().map(|r| r.name.as_str()).collect();
+
+    let mut leftovers: Vec<SnapLeftover> = rows
+        .iter()
+        .filter(|r| r.notes.split(',').any(|n| n == "disabled"))
+        .map(|r| SnapLeftover::DisabledRevision {
+            name: r.name.clone(),
+            version: r.version.clone(),
+            revision: r.revision.clone(),
+            size: std::fs::metadata(format!("{}/{}_{}.snap", SNAPS_DIR, r.name, r.revision))
+                .map(|m| m.len())
+                .ok(),
+        })
+        .collect();
+
+    if let Some(home) = dirs::home_dir()
+        && let Ok(entries) = std::fs::read_dir(home.join("snap"))
+    {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = path.file_name().and_then(|s| s.to_str()).unwrap_or("").to_string();
+
+            if !name.is_empty() && !installed.contains(name.as_str()) {
+                leftovers.push(SnapLeftover::UserData { name, path });
+            }
+        }
+    }
+
+    Ok(leftovers)
+}
+
+struct SnapRow {
+    name: String,
+    version: String,
+    revision: String,
+    notes: String,
+}
+
+/// Parses `snap list --all`:
+/// `Name  Version  Rev  Tracking  Publisher  Notes`
+fn parse_snap_list(table: &str) -> Vec<SnapRow> {
+    let mut lines = table.lines();
+    let Some(header) = lines.next() else {
+        return Vec::new();
+    };
+
+    let columns: Vec<&str> = header.split_whitespace().collect();
+    let column = |name: &str| columns.iter().position(|c| *c == name);
+    let (Some(name_col), Some(version_col), Some(rev_col), Some(notes_col)) =
+        (column("Name"), column("Version"), column("Rev"), column("Notes"))
+    else {
+        return Vec::new();
+    };
+
+    lines
+        .filter_map(|line| {
+            let cells: Vec<&str> = line.split_whitespace().collect();
+            Some(SnapRow {
+                name: cells.get(name_col)?.to_string(),
+                version: cells.get(version_col)?.to_string(),
+                revision: cells.get(rev_col)?.to_string(),
+                notes: cells.get(notes_col)?.to_string(),
+            })
+        })
+        .collect()
+}
@@ -1,4 +1,5 @@
-use crate::types::SweepItem;
+use crate::backend::{self, ScanScope};
+use crate::types::{Package, SweepItem};
 use crate::config::Config;
 use anyhow::Result;
 use crossterm::{
@@ -20,8 +21,10 @@ pub struct App {
     selected: Vec<bool>,
     cursor: usize,
     viewport_start: usize,
+    show_details: bool,
     dry_run: bool,
     config: Config,
+    scope: ScanScope,
 }
 
 impl TerminalGuard {
@@ -40,19 +43,27 @@ impl Drop for TerminalGuard {
 }
 
 impl App {
-    pub fn new(items: Vec<SweepItem>, dry_run: bool, config: Config) -> Self {
+    pub fn new(mut items: Vec<SweepItem>, dry_run: bool, config: Config, scope: ScanScope) -> Self {
+        if config.hide_protected {
+            items.retain(|item| !item.is_protected());
+        }
+
         let selected = vec![false; items.len()];
         Self {
             items,
             selected,
             cursor: 0,
             viewport_start: 0,
+            show_details: true,
             dry_run,
             config,
+            scope,
         }
     }
 
-    pub fn run(&mut self) -> Result<()> {
+    /// Runs the selection UI and removes what was confirmed. Returns whether
+    /// any packages were actually removed.
+    pub fn run(&mut self) -> Result<bool> {
         let guard = TerminalGuard::enter()?;
         let backend = CrosstermBackend::new(stdout());
         let mut terminal = Terminal::new(backend)?;
@@ -61,35 +72,41 @@ impl App {
         let result = loop {
             terminal.draw(|frame| self.render(frame))?;
 
-            if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
-                    match key.code {
-                        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break false,
-                        c if self.config.keybindings.quit.contains(&c) => break false,
-                        c if self.config.keybindings.select.contains(&c) => {
-                            if !self.items.is_empty() {
-                                self.selected[self.cursor] = !self.selected[self.cursor];
-                            }
-                        },
-                        c if self.config.keybindings.confirm.contains(&c) => break true,
-                        c if self.config.keybindings.cursor_up.contains(&c) => self.cursor = self.cursor.saturating_sub(1),
-                        c if self.config.keybindings.cursor_down.contains(&c) => self.cursor = (self.cursor + 1).min(self.items.len().saturating_sub(1)),
-                        c if self.config.keybindings.select_all.contains(&c) => {
-                            let is_all = self.selected.iter().all(|&x| x == true);
-                            self.selected.fill(!is_all);
-                        },
-                        _ => {},
-                    }
+            if let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+            {
+                match key.code {
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break false,
+                    c if self.config.keybindings.quit.contains(&c) => break false,
+                    c if self.config.keybindings.select.contains(&c)
+                        && self.items.get(self.cursor).is_some_and(|item| !item.is_protected()) =>
+                    {
+                        self.selected[self.cursor] = !self.selected[self.cursor];
+                    },
+                    c if self.config.keybindings.confirm.contains(&c) => break true,
+                    c if self.config.keybindings.cursor_up.contains(&c) => self.cursor = self.cursor.saturating_sub(1),
+                    c if self.config.keybindings.cursor_down.contains(&c) => self.cursor = (self.cursor + 1).min(self.items.len().saturating_sub(1)),
+                    c if self.config.keybindings.details.contains(&c) => self.show_details = !self.show_details,
+                    c if self.config.keybindings.select_all.contains(&c) => {
+                        let selectable: Vec<usize> = (0..self.items.len())
+                            .filter(|&i| !self.items[i].is_protected())
+                            .collect();
+                        let is_all = selectable.iter().all(|&i| self.selected[i]);
+                        for i in selectable {
+                            self.selected[i] = !is_all;
+                        }
+                    },
+                    _ => {},
                 }
             }
         };
 
-        if result {
-            drop(guard);
-            self.confirm_and_remove()?;
+        if !result {
+            return Ok(false);
         }
 
-        Ok(())
+        drop(guard);
+        self.confirm_and_remove()
     }
 
     fn adjust_viewport(&mut self, visible_height: usize) {
@@ -109,7 +126,12 @@ impl App {
     }
 
     fn render(&mut self, frame: &mut Frame) {
-        let area = frame.area();
+        let details = self.details_text();
+        let [area, details_area] = Layout::vertical([
+            Constraint::Min(3),
+            Constraint::Length(if details.is_some() { 6 } else { 0 }),
+        ])
+        .areas(frame.area());
         let visible_height = area.height.saturating_sub(2) as usize;
 
         self.adjust_viewport(visible_height);
@@ -128,10 +150,23 @@ impl App {
         let end = (self.viewport_start + visible_height).min(self.items.len());
         let visible_items: Vec<ListItem> = (self.viewport_start..end)
             .map(|i| {
-                let prefix = if self.selected[i] { "✓ " } else { "  " };
+                let protected = self.items[i].is_protected();
+                let prefix = if protected {
+                    "🔒"
+                } else if self.selected[i] {
+                    "✓ "
+                } else {
+                    "  "
+                };
                 let icon = match &self.items[i] {
                     SweepItem::Package(_) => &self.config.theme.package_icon,
                     SweepItem::HomeArtifact(_) => &self.config.theme.artifact_icon,
+                    SweepItem::ConfigLeftover(_) => &self.config.theme.leftover_icon,
+                    SweepItem::Flatpak(_) => &self.config.theme.flatpak_icon,
+                    SweepItem::Snap(_) => &self.config.theme.snap_icon,
+                    SweepItem::Nix(_) => &self.config.theme.nix_icon,
+                    SweepItem::CacheFile(_) => &self.config.theme.cache_icon,
+                    SweepItem::Kernel(_) => &self.config.theme.kernel_icon,
                 };
 
                 let line = Line::from(format!("{}{} {}", prefix, icon, self.items[i]));
@@ -139,6 +174,8 @@ impl App {
                     Style::new()
                         .bg(self.config.theme.selected_bg.0)
                         .fg(Color::Black)
+                } else if protected {
+                    Style::new().fg(Color::DarkGray)
                 } else {
                     Style::new() 
                 };
@@ -148,9 +185,51 @@ impl App {
 
         let list = List::new(visible_items).block(block).highlight_symbol(">> ");
         frame.render_widget(list, area);
+
+        if let Some(details) = details {
+            let block = Block::bordered()
+                .title("Why?")
+                .border_style(Style::new().fg(self.config.theme.selected_bg.0));
+            let paragraph = Paragraph::new(details).block(block).wrap(Wrap { trim: true });
+            frame.render_widget(paragraph, details_area);
+        }
     }
 
-    fn confirm_and_remove(&self) -> Result<()> {
+    /// Explanation for the package under the cursor, if the pane is shown
+    /// and the backend could explain it.
+    fn details_text(&self) -> Option<Vec<Line<'static>>> {
+        if !self.show_details {
+            return None;
+        }
+
+        let SweepItem::Package(pkg) = self.items.get(self.cursor)? else {
+            return None;
+        };
+        let why = pkg.explanation.as_ref()?;
+
+        let list_or = |names: &[String], empty: &str| {
+            if names.is_empty() { empty.to_string() } else { names.join(", ") }
+        };
+
+        let mut lines = vec![Line::from(format!(
+            "Auto-installed: {}    Required by: {}",
+            if why.auto_installed { "yes" } else { "no" },
+            list_or(&why.required_by, "nothing installed"),
+        ))];
+
+        if let Some(origin) = &why.pulled_in_by {
+            lines.push(Line::from(format!("Pulled in by: {}", origin)));
+        }
+
+        lines.push(Line::from(format!(
+            "Removing it also orphans: {}",
+            list_or(&why.cascade, "nothing else"),
+        )));
+
+        Some(lines)
+    }
+
+    fn confirm_and_remove(&self) -> Result<bool> {
         let selected_items: Vec<_> = self
             .items
             .iter()
@@ -161,7 +240,17 @@ impl App {
 
         if selected_items.is_empty() {
             println!("\nℹ️  Nothing selected.");
-            return Ok(());
+            return Ok(false);
+        }
+
+        let mut batches: Vec<Vec<&Package>> = Vec::new();
+        for item in &selected_items {
+            if let SweepItem::Package(pkg) = item {
+                match batches.iter_mut().find(|batch| batch[0].system == pkg.system) {
+                    Some(batch) => batch.push(pkg),
+                    None => batches.push(vec![pkg]),
+                }
+            }
         }
 
         if self.dry_run {
@@ -169,45 +258,106 @@ impl App {
             for item in &selected_items {
                 println!("  - {}", item);
             }
+            print_simulations(&batches, &self.scope);
         } else {
             println!("\n⚠️  PERMANENTLY REMOVE THE FOLLOWING ITEMS?");
             for item in &selected_items {
                 println!("  - {}", item)
             }
+            print_simulations(&batches, &self.scope);
             println!("\nConfirm? [y/N]: ");
 
             let confirmed = loop {
-                if let Event::Key(key) = event::read()? {
-                    if key.kind == KeyEventKind::Press {
-                        match key.code {
-                            KeyCode::Char('y') | KeyCode::Char('Y') => break true,
-                            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc | KeyCode::Char('q') => break false,
-                            _ => continue,
-                        }
+                if let Event::Key(key) = event::read()?
+                    && key.kind == KeyEventKind::Press
+                {
+                    match key.code {
+                        KeyCode::Char('y') | KeyCode::Char('Y') => break true,
+                        KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc | KeyCode::Char('q') => break false,
+                        _ => continue,
                     }
                 }
             };
 
             if !confirmed {
                 println!("\n❌ Canceled.");
-                return Ok(());
+                return Ok(false);
             }
 
             println!("\n🧹 Removing...");
+            for batch in &batches {
+                let names: Vec<&str> = batch.iter().map(|p| p.name.as_str()).collect();
+                println!("📦 Removing packages: {}", names.join(", "));
+                Package::remove_batch(batch, false, &self.config.su_command)?;
+            }
+
             for item in &selected_items {
                 match item {
-                    SweepItem::Package(pkg) => {
-                        println!("📦 Removing package: {}", pkg.name);
-                        pkg.remove(false, &self.config.su_command)?;
-                    }
+                    SweepItem::Package(_) => {}
                     SweepItem::HomeArtifact(art) => {
-                        println!("🏠 Removing: {}", art.path.display());
+                        match &art.associated_package {
+                            Some(pkg) => println!("🏠 Removing: {} (from {})", art.path.display(), pkg),
+                            None => println!("🏠 Removing: {}", art.path.display()),
+                        }
                         art.remove(false)?;
                     }
+                    SweepItem::ConfigLeftover(leftover) => {
+                        println!("📝 Removing: {}", leftover.path.display());
+                        leftover.remove(false, &self.config.su_command)?;
+                    }
+                    SweepItem::Flatpak(fp) => {
+                        println!("🧊 Removing: {}", fp);
+                        fp.remove(false)?;
+                    }
+                    SweepItem::Snap(snap) => {
+                        println!("🫧 Removing: {}", snap);
+                        snap.remove(false, &self.config.su_command)?;
+                    }
+                    SweepItem::Nix(nix) => {
+                        println!("❄️ Removing: {}", nix);
+                        nix.remove(false, &self.config.su_command)?;
+                    }
+                    SweepItem::CacheFile(cache) => {
+                        println!("💾 Removing: {}", cache.path.display());
+                        cache.remove(false, &self.config.su_command)?;
+                    }
+                    SweepItem::Kernel(kernel) => {
+                        println!("🐧 Removing kernel: {}", kernel.release);
+                        kernel.remove(false, &self.config.su_command)?;
+                    }
+                }
+            }
+            println!("\n✅ Done!");
+            return Ok(selected_items.iter().any(|item| matches!(item, SweepItem::Package(_) | SweepItem::Kernel(_))));
+        }
+        Ok(false)
+    }
+}
+
+/// Shows what each package manager says it would really remove, calling out
+/// packages that were not selected but would go along with the selection.
+fn print_simulations(batches: &[Vec<&Package>], scope: &ScanScope) {
+    // The simulating tools only know the running system, not the one under `--root`
+    if !scope.is_host() {
+        return;
+    }
+
+    for batch in batches {
+        let backend = backend::for_system(&batch[0].system);
+        match backend.simulate_removal(batch) {
+            Ok(Some(removed)) => {
+                println!("\n🔎 {} simulation removes {} package(s)", backend.name(), removed.len());
+                let extra: Vec<&str> = removed
+                    .iter()
+                    .filter(|name| !batch.iter().any(|p| &p.name == *name))
+                    .map(|name| name.as_str())
+                    .collect();
+                if !extra.is_empty() {
+                    println!("⚠️  Also pulled out: {}", extra.join(", "));
                 }
             }
-            println!("\n✅ Done!")
+            Ok(None) => {}
+            Err(err) => println!("\n⚠️  {} simulation failed: {}", backend.name(), err),
         }
-        Ok(())
     }
 }
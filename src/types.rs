@@ -1,10 +1,16 @@
-use std::process::Command;
+use crate::backend;
 use serde::{Deserialize, Deserializer};
+use std::process::Command;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum OS {
     Void,
     Debian,
+    Arch,
+    Fedora,
+    Alpine,
+    OpenSuse,
+    Gentoo,
     Unsupported,
 }
 
@@ -12,6 +18,12 @@ pub enum OS {
 pub enum SweepItem {
     Package(Package),
     HomeArtifact(HomeArtifact),
+    ConfigLeftover(ConfigLeftover),
+    Flatpak(FlatpakLeftover),
+    Snap(SnapLeftover),
+    Nix(NixLeftover),
+    CacheFile(CacheFile),
+    Kernel(Kernel),
 }
 
 #[derive(Debug, Clone)]
@@ -20,13 +32,37 @@ pub struct Package {
     pub version: String,
     pub description: String,
     pub installed: bool,
+    /// Installed size in bytes, when the backend knows it.
+    pub installed_size: Option<u64>,
     pub system: PackageSystem,
+    /// Why the package was flagged, for backends that expose a dependency graph.
+    pub explanation: Option<Explanation>,
+    /// Essential to the system or listed in `protected_packages`; never removable.
+    pub protected: bool,
+    /// Portage category (`sys-kernel`), kept apart from the name for atoms.
+    pub category: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Explanation {
+    pub auto_installed: bool,
+    /// Installed packages that still depend on it.
+    pub required_by: Vec<String>,
+    /// What originally installed it as a dependency, if the backend keeps a log.
+    pub pulled_in_by: Option<String>,
+    /// Auto-installed packages that become orphans once it is removed.
+    pub cascade: Vec<String>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum PackageSystem {
     Xbps,   // Void Linux
     Dpkg,   // Debian/Ubuntu/Mint etc.
+    Pacman, // Arch Linux
+    Rpm,    // Fedora/RHEL (dnf)
+    Apk,    // Alpine
+    Zypper, // openSUSE
+    Portage, // Gentoo
 }
 
 #[derive(Debug, Clone)]
@@ -34,6 +70,92 @@ pub struct HomeArtifact {
     pub path: std::path::PathBuf,
     pub associated_package: Option<String>,
     pub reason: String,
+    /// How sure sweep is that nothing uses this anymore, in percent.
+    pub confidence: u8,
+}
+
+/// A config file the package manager left next to the real one,
+/// e.g. `/etc/pacman.conf.pacnew`.
+#[derive(Debug, Clone)]
+pub struct ConfigLeftover {
+    pub path: std::path::PathBuf,
+    pub reason: String,
+}
+
+/// An installed kernel that is neither running nor among the newest ones kept.
+#[derive(Debug, Clone)]
+pub struct Kernel {
+    pub release: String,
+    /// Packages shipping this kernel, if the package manager tracks them individually.
+    pub packages: Vec<String>,
+    /// Module directory and `/boot` files of this release.
+    pub files: Vec<std::path::PathBuf>,
+    pub size: u64,
+    pub system: PackageSystem,
+}
+
+/// A downloaded package, source tarball or similar file kept in a
+/// package manager cache.
+#[derive(Debug, Clone)]
+pub struct CacheFile {
+    pub path: std::path::PathBuf,
+    /// Files removed together with `path`, e.g. detached signatures.
+    pub related: Vec<std::path::PathBuf>,
+    /// Combined size of `path` and `related`.
+    pub size: u64,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum FlatpakLeftover {
+    /// A runtime or extension no installed app needs.
+    UnusedRef {
+        id: String,
+        branch: String,
+        installation: FlatpakInstallation,
+    },
+    /// `~/.var/app/<app_id>` of an app that is no longer installed.
+    AppData {
+        app_id: String,
+        path: std::path::PathBuf,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FlatpakInstallation {
+    User,
+    System,
+}
+
+#[derive(Debug, Clone)]
+pub enum SnapLeftover {
+    /// An old revision snapd keeps after a refresh.
+    DisabledRevision {
+        name: String,
+        version: String,
+        revision: String,
+        size: Option<u64>,
+    },
+    /// `~/snap/<name>` of a snap that is no longer installed.
+    UserData {
+        name: String,
+        path: std::path::PathBuf,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub enum NixLeftover {
+    /// A non-current generation; `profile` is `None` for the user's default profile.
+    Generation {
+        profile: Option<std::path::PathBuf>,
+        id: u32,
+        created: String,
+    },
+    /// Unreachable store paths a garbage collection would delete.
+    Garbage {
+        paths: usize,
+        size: u64,
+    },
 }
 
 impl<'de> Deserialize<'de> for OS {
@@ -45,64 +167,361 @@ impl<'de> Deserialize<'de> for OS {
         match s.to_lowercase().as_str() {
             "void" => Ok(OS::Void),
             "debian" => Ok(OS::Debian),
+            "arch" => Ok(OS::Arch),
+            "fedora" | "rhel" => Ok(OS::Fedora),
+            "alpine" => Ok(OS::Alpine),
+            "opensuse" | "suse" => Ok(OS::OpenSuse),
+            "gentoo" => Ok(OS::Gentoo),
             _ => Ok(OS::Unsupported),
         }
     }
 }
 
+impl SweepItem {
+    pub fn is_protected(&self) -> bool {
+        matches!(self, SweepItem::Package(p) if p.protected)
+    }
+}
+
 impl Package {
+    /// Removes `pkgs`, which must all belong to the same package system, in
+    /// one package manager invocation. If that fails, each package is retried
+    /// on its own so the error names the ones that could not be removed.
+    pub fn remove_batch(pkgs: &[&Package], dry_run: bool, su_command: &str) -> anyhow::Result<()> {
+        let Some(first) = pkgs.first() else {
+            return Ok(());
+        };
+        let backend = backend::for_system(&first.system);
+
+        if dry_run {
+            println!("  [DRY] {}", backend.dry_run_plan(pkgs));
+            return Ok(());
+        }
+
+        let Err(err) = backend.remove(pkgs, su_command) else {
+            return Ok(());
+        };
+        if pkgs.len() == 1 {
+            return Err(err);
+        }
+
+        println!("⚠️  {}; retrying one package at a time", err);
+        let mut failed = Vec::new();
+        for pkg in pkgs {
+            if let Err(err) = backend.remove(&[pkg], su_command) {
+                println!("❌ {}", err);
+                failed.push(pkg.name.as_str());
+            }
+        }
+
+        if !failed.is_empty() {
+            anyhow::bail!("Failed to remove package: {}", failed.join(", "));
+        }
+
+        Ok(())
+    }
+}
+
+impl HomeArtifact {
+    pub fn remove(&self, dry_run: bool) -> anyhow::Result<()> {
+        if dry_run {
+            println!("  [DRY] rm -rf {}", self.path.display());
+        } else {
+            if self.path.is_dir() {
+                std::fs::remove_dir_all(&self.path)?;
+            } else if self.path.exists() {
+                std::fs::remove_file(&self.path)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ConfigLeftover {
     pub fn remove(&self, dry_run: bool, su_command: &str) -> anyhow::Result<()> {
-        match self.system {
-            PackageSystem::Xbps => {
+        let path = self.path.to_string_lossy();
+
+        if dry_run {
+            println!("  [DRY] rm -f {}", path);
+        } else {
+            let status = Command::new(su_command)
+                .args(["rm", "-f", &path])
+                .status()?;
+
+            if !status.success() {
+                anyhow::bail!("Failed to remove file: {}", path);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Orders versions such as `6.1.0-9-amd64` < `6.1.0-13-amd64` or
+/// `1.2.8_1` < `1.2.10_1` by their numeric components.
+pub fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    let numbers = |s: &str| -> Vec<u64> {
+        s.split(|c: char| !c.is_ascii_digit())
+            .filter_map(|n| n.parse().ok())
+            .collect()
+    };
+
+    numbers(a).cmp(&numbers(b)).then_with(|| a.cmp(b))
+}
+
+/// Formats a byte count as a human-readable size, e.g. `12.3 MiB`.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+impl Kernel {
+    pub fn remove(&self, dry_run: bool, su_command: &str) -> anyhow::Result<()> {
+        let command = backend::for_system(&self.system).kernel_removal_command(self);
+
+        if dry_run {
+            if !command.is_empty() {
+                println!("  [DRY] {}", command.join(" "));
+            }
+            for file in &self.files {
+                println!("  [DRY] rm -rf {}", file.display());
+            }
+            return Ok(());
+        }
+
+        if !command.is_empty() {
+            let status = Command::new(su_command)
+                .args(&command)
+                .status()?;
+
+            if !status.success() {
+                anyhow::bail!("Failed to remove kernel: {}", self.release);
+            }
+        }
+
+        // Out-of-tree modules (dkms) and stale initramfs images survive the package removal
+        let leftovers: Vec<_> = self.files.iter().filter(|f| f.exists()).collect();
+        if !leftovers.is_empty() {
+            let status = Command::new(su_command)
+                .args(["rm", "-rf"])
+                .args(&leftovers)
+                .status()?;
+
+            if !status.success() {
+                anyhow::bail!("Failed to remove files of kernel: {}", self.release);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl CacheFile {
+    pub fn remove(&self, dry_run: bool, su_command: &str) -> anyhow::Result<()> {
+        let paths: Vec<_> = std::iter::once(&self.path).chain(&self.related).collect();
+
+        if dry_run {
+            for path in &paths {
+                println!("  [DRY] rm -f {}", path.display());
+            }
+        } else {
+            let status = Command::new(su_command)
+                .args(["rm", "-f"])
+                .args(&paths)
+                .status()?;
+
+            if !status.success() {
+                anyhow::bail!("Failed to remove file: {}", self.path.display());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl FlatpakInstallation {
+    pub fn flag(&self) -> &'static str {
+        match self {
+            FlatpakInstallation::User => "--user",
+            FlatpakInstallation::System => "--system",
+        }
+    }
+}
+
+impl FlatpakLeftover {
+    pub fn remove(&self, dry_run: bool) -> anyhow::Result<()> {
+        match self {
+            FlatpakLeftover::UnusedRef { id, branch, installation } => {
+                let flatpak_ref = format!("{}//{}", id, branch);
                 if dry_run {
-                    println!("  [DRY] xbps-remove -y {}", self.name)
+                    println!("  [DRY] flatpak uninstall -y {} {}", installation.flag(), flatpak_ref);
                 } else {
-                    let status = Command::new(su_command)
-                        .args(["xbps-remove", "-y", &self.name])
+                    let status = Command::new("flatpak")
+                        .args(["uninstall", "-y", "--noninteractive", installation.flag(), &flatpak_ref])
                         .status()?;
+
                     if !status.success() {
-                        anyhow::bail!("Failed to remove package: {}", self.name);
+                        anyhow::bail!("Failed to uninstall flatpak: {}", flatpak_ref);
                     }
                 }
             },
-            PackageSystem::Dpkg => {
+            FlatpakLeftover::AppData { path, .. } => {
                 if dry_run {
-                    println!("  [DRY] apt purge -y {}", self.name);
+                    println!("  [DRY] rm -rf {}", path.display());
+                } else if path.exists() {
+                    std::fs::remove_dir_all(path)?;
+                }
+            },
+        }
+
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for FlatpakLeftover {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FlatpakLeftover::UnusedRef { id, branch, installation } => {
+                let scope = match installation {
+                    FlatpakInstallation::User => "user",
+                    FlatpakInstallation::System => "system",
+                };
+                write!(f, "{}//{} (Unused {} runtime)", id, branch, scope)
+            },
+            FlatpakLeftover::AppData { app_id, path } => {
+                write!(f, "{} (Data of uninstalled app {})", path.display(), app_id)
+            },
+        }
+    }
+}
+
+impl SnapLeftover {
+    pub fn remove(&self, dry_run: bool, su_command: &str) -> anyhow::Result<()> {
+        match self {
+            SnapLeftover::DisabledRevision { name, revision, .. } => {
+                let revision_arg = format!("--revision={}", revision);
+                if dry_run {
+                    println!("  [DRY] snap remove {} {}", revision_arg, name);
                 } else {
                     let status = Command::new(su_command)
-                        .args(["apt", "purge", "-y", &self.name])
+                        .args(["snap", "remove", &revision_arg, name])
                         .status()?;
 
                     if !status.success() {
-                        anyhow::bail!("Failed to purge package: {}", self.name);
+                        anyhow::bail!("Failed to remove snap revision: {} ({})", name, revision);
                     }
                 }
             },
+            SnapLeftover::UserData { path, .. } => {
+                if dry_run {
+                    println!("  [DRY] rm -rf {}", path.display());
+                } else if path.exists() {
+                    std::fs::remove_dir_all(path)?;
+                }
+            },
         }
 
         Ok(())
     }
 }
 
-impl HomeArtifact {
-    pub fn remove(&self, dry_run: bool) -> anyhow::Result<()> {
-        if dry_run {
-            println!("  [DRY] rm -rf {}", self.path.display());
-        } else {
-            if self.path.exists() {
-                std::fs::remove_dir_all(&self.path)?;
-            }
+impl std::fmt::Display for SnapLeftover {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapLeftover::DisabledRevision { name, version, revision, size } => {
+                write!(f, "{} {} rev {} (Disabled revision", name, version, revision)?;
+                if let Some(size) = size {
+                    write!(f, ", {}", format_size(*size))?;
+                }
+                write!(f, ")")
+            },
+            SnapLeftover::UserData { name, path } => {
+                write!(f, "{} (Data of uninstalled snap {})", path.display(), name)
+            },
+        }
+    }
+}
+
+impl NixLeftover {
+    pub fn remove(&self, dry_run: bool, su_command: &str) -> anyhow::Result<()> {
+        match self {
+            NixLeftover::Generation { profile, id, .. } => {
+                let mut args = vec!["nix-env".to_string()];
+                if let Some(profile) = profile {
+                    args.extend(["-p".to_string(), profile.to_string_lossy().to_string()]);
+                }
+                args.extend(["--delete-generations".to_string(), id.to_string()]);
+
+                if dry_run {
+                    println!("  [DRY] {}", args.join(" "));
+                } else {
+                    // Only the system profile is owned by root
+                    let (program, args) = match profile {
+                        Some(_) => (su_command, &args[..]),
+                        None => (args[0].as_str(), &args[1..]),
+                    };
+
+                    if !Command::new(program).args(args).status()?.success() {
+                        anyhow::bail!("Failed to delete nix generation: {}", id);
+                    }
+                }
+            },
+            NixLeftover::Garbage { .. } => {
+                if dry_run {
+                    println!("  [DRY] nix-collect-garbage");
+                } else {
+                    let status = Command::new("nix-collect-garbage").status()?;
+                    if !status.success() {
+                        anyhow::bail!("nix-collect-garbage failed");
+                    }
+                }
+            },
         }
 
         Ok(())
     }
 }
 
+impl std::fmt::Display for NixLeftover {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NixLeftover::Generation { profile, id, created } => match profile {
+                Some(profile) => write!(f, "{} generation {} (from {})", profile.display(), id, created),
+                None => write!(f, "user profile generation {} (from {})", id, created),
+            },
+            NixLeftover::Garbage { paths, size } => {
+                write!(f, "nix store garbage ({} paths, {} reclaimable)", paths, format_size(*size))
+            },
+        }
+    }
+}
+
 impl std::fmt::Display for SweepItem {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            SweepItem::Package(p) => write!(f, "{} ({})", p.name, p.description),
-            SweepItem::HomeArtifact(a) => write!(f, "{} ({})", a.path.display(), a.reason),
+            SweepItem::Package(p) => {
+                write!(f, "{} ({})", p.name, backend::for_system(&p.system).describe(p))
+            },
+            SweepItem::HomeArtifact(a) => write!(f, "{} ({}, {}% sure)", a.path.display(), a.reason, a.confidence),
+            SweepItem::ConfigLeftover(c) => write!(f, "{} ({})", c.path.display(), c.reason),
+            SweepItem::Flatpak(fp) => write!(f, "{}", fp),
+            SweepItem::Snap(snap) => write!(f, "{}", snap),
+            SweepItem::Nix(nix) => write!(f, "{}", nix),
+            SweepItem::Kernel(k) => write!(f, "linux {} (Old kernel, {})", k.release, format_size(k.size)),
+            SweepItem::CacheFile(c) => write!(f, "{} ({}, {})", c.path.display(), c.reason, format_size(c.size)),
         }
     }
 }
@@ -1,40 +1,478 @@
-use crate::types::{Package, PackageSystem};
+use crate::backend::{self, CachedPackage, PackageBackend, ScanScope};
+use crate::depgraph::DependencyGraph;
+use crate::kernel;
+use crate::types::{CacheFile, Kernel, Package, PackageSystem, OS};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
-pub fn list_orphans() -> anyhow::Result<Vec<Package>> {
-    let output = Command::new("xbps-query")
-        .args(["-O"])
-        .output()?;
+const PKGDB_PATH: &str = "var/db/xbps/pkgdb-0.38.plist";
+const CACHE_PATH: &str = "var/cache/xbps";
 
-    if !output.status.success() {
-        anyhow::bail!("xbps-query failed");
+pub struct Xbps;
+
+/// An installed package as recorded in the xbps pkgdb.
+#[derive(Debug, Clone)]
+pub struct XbpsPackage {
+    pub name: String,
+    /// `version_revision`, e.g. `1.2.3_1`.
+    pub version: String,
+    pub short_desc: String,
+    pub installed_size: u64,
+    pub automatic_install: bool,
+    /// Names of the packages this one depends on.
+    pub run_depends: Vec<String>,
+    /// Virtual packages this one provides, e.g. `java-runtime`.
+    pub provides: Vec<String>,
+    /// Names of installed packages that depend on this one.
+    pub reverse_depends: Vec<String>,
+}
+
+/// `.<name>-files.plist`: what a package installed, grouped by kind.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct FilesPlist {
+    files: Vec<FilesEntry>,
+    links: Vec<FilesEntry>,
+    conf_files: Vec<FilesEntry>,
+    dirs: Vec<FilesEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FilesEntry {
+    file: PathBuf,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct PkgdbEntry {
+    pkgver: String,
+    short_desc: String,
+    installed_size: u64,
+    #[serde(rename = "automatic-install")]
+    automatic_install: bool,
+    run_depends: Vec<String>,
+    provides: Vec<String>,
+}
+
+impl PackageBackend for Xbps {
+    fn name(&self) -> &'static str {
+        "xbps"
     }
 
-    let stdout = String::from_utf8(output.stdout)?;
-    let mut packages = Vec::new();
+    fn os(&self) -> OS {
+        OS::Void
+    }
 
-    for line in stdout.lines() {
-        if let Some(name_ver) = line.split_whitespace().next() {
-            let (name, version) = split_name_version(name_ver);
-            packages.push(Package {
-                name,
-                version,
-                description: "Orphaned package".to_string(),
-                installed: true,
-                system: PackageSystem::Xbps,
-            });
+    fn system(&self) -> PackageSystem {
+        PackageSystem::Xbps
+    }
+
+    fn detect(&self, root: &Path) -> bool {
+        root.join("usr/bin/xbps-query").exists()
+    }
+
+    fn list_candidates(&self, scope: &ScanScope) -> anyhow::Result<Vec<Package>> {
+        if scope.orphans {
+            list_orphans(&scope.root)
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    fn removal_command(&self, pkgs: &[&Package]) -> Vec<String> {
+        let mut cmd = vec!["xbps-remove".to_string(), "-y".to_string()];
+        cmd.extend(pkgs.iter().map(|p| p.name.clone()));
+        cmd
+    }
+
+    fn simulate_removal(&self, pkgs: &[&Package]) -> anyhow::Result<Option<Vec<String>>> {
+        let output = Command::new("xbps-remove")
+            .arg("-n")
+            .args(pkgs.iter().map(|p| &p.name))
+            .output()?;
+
+        if !output.status.success() {
+            anyhow::bail!("xbps-remove -n failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+
+        Ok(Some(parse_simulation(&String::from_utf8(output.stdout)?)))
+    }
+
+    fn dependency_graph(&self, scope: &ScanScope) -> anyhow::Result<Option<DependencyGraph>> {
+        let mut graph = DependencyGraph::default();
+
+        for pkg in read_pkgdb(&scope.root)? {
+            graph.add_package(&pkg.name, pkg.automatic_install);
+            for dependent in &pkg.reverse_depends {
+                graph.add_dependency(dependent, &pkg.name);
+            }
+        }
+
+        Ok(Some(graph))
+    }
+
+    /// `base-system` and everything it pulls in, directly or not.
+    fn essential_packages(&self, scope: &ScanScope) -> anyhow::Result<HashSet<String>> {
+        let pkgdb: HashMap<String, XbpsPackage> = read_pkgdb(&scope.root)?
+            .into_iter()
+            .map(|p| (p.name.clone(), p))
+            .collect();
+
+        let mut essential = HashSet::new();
+        let mut pending = vec!["base-system".to_string()];
+        while let Some(name) = pending.pop() {
+            let Some(pkg) = pkgdb.get(&name) else {
+                continue;
+            };
+
+            if essential.insert(name) {
+                pending.extend(pkg.run_depends.iter().cloned());
+            }
         }
+
+        Ok(essential)
+    }
+
+    fn installed_packages(&self, scope: &ScanScope) -> anyhow::Result<Vec<String>> {
+        Ok(read_pkgdb(&scope.root)?.into_iter().map(|p| p.name).collect())
+    }
+
+    fn package_files(&self, root: &Path, name: &str) -> anyhow::Result<Vec<PathBuf>> {
+        read_files_plist(root, name)
     }
 
+    fn list_kernels(&self, scope: &ScanScope) -> anyhow::Result<Vec<Kernel>> {
+        list_old_kernels(scope)
+    }
+
+    fn kernel_removal_command(&self, kernel: &Kernel) -> Vec<String> {
+        vec!["vkpurge".to_string(), "rm".to_string(), kernel.release.clone()]
+    }
+
+    fn list_caches(&self, scope: &ScanScope) -> anyhow::Result<Vec<CacheFile>> {
+        list_stale_cache(scope)
+    }
+}
+
+/// Automatically installed packages nothing depends on, as `xbps-query -O` reports them.
+pub fn list_orphans(root: &Path) -> anyhow::Result<Vec<Package>> {
+    let packages = read_pkgdb(root)?
+        .into_iter()
+        .filter(|p| p.automatic_install && p.reverse_depends.is_empty())
+        .map(|p| Package {
+            name: p.name,
+            version: p.version,
+            description: p.short_desc,
+            installed: true,
+            installed_size: Some(p.installed_size),
+            system: PackageSystem::Xbps,
+            explanation: None,
+            protected: false,
+            category: None,
+        })
+        .collect();
+
     Ok(packages)
 }
 
-fn split_name_version(s: &str) -> (String, String) {
-    if let Some(pos) = s.rfind("-") {
-        let name = s[..pos].to_string();
-        let version = s[(pos + 1)..].to_string();
-        (name, version)
+/// Kernels `vkpurge list` offers, minus the newest `scope.keep_kernels`.
+/// Void kernels are explicitly installed, so they never show up as orphans.
+/// Under `--root`, where vkpurge cannot look, the module directories are listed instead.
+pub fn list_old_kernels(scope: &ScanScope) -> anyhow::Result<Vec<Kernel>> {
+    let running = kernel::running_release(&scope.root)?;
+
+    let installed: Vec<String> = if scope.is_host() {
+        let output = Command::new("vkpurge")
+            .args(["list"])
+            .output()?;
+
+        if !output.status.success() {
+            anyhow::bail!("vkpurge list failed");
+        }
+
+        let mut installed: Vec<String> = String::from_utf8(output.stdout)?
+            .lines()
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty())
+            .collect();
+        // vkpurge never lists the running kernel, but it counts towards the newest ones kept
+        installed.push(running.clone());
+        installed
     } else {
-        (s.to_string(), "unknown".to_string())
+        std::fs::read_dir(scope.root.join("usr/lib/modules"))
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect()
+    };
+
+    let kernels = kernel::select_removable(installed, &running, scope.keep_kernels)
+        .into_iter()
+        .map(|release| {
+            let files = kernel::release_files(&scope.root, &release);
+            Kernel {
+                size: kernel::disk_usage(&files),
+                release,
+                packages: Vec::new(),
+                files,
+                system: PackageSystem::Xbps,
+            }
+        })
+        .collect();
+
+    Ok(kernels)
+}
+
+/// Cached `<pkgver>.<arch>.xbps` files whose version is not installed,
+/// minus the newest `scope.keep_cached` of each package.
+pub fn list_stale_cache(scope: &ScanScope) -> anyhow::Result<Vec<CacheFile>> {
+    let Ok(entries) = std::fs::read_dir(scope.root.join(CACHE_PATH)) else {
+        return Ok(Vec::new());
+    };
+
+    let installed: HashMap<String, String> = read_pkgdb(&scope.root)?
+        .into_iter()
+        .map(|p| (p.name, p.version))
+        .collect();
+
+    let mut cached = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Some(file_name) = path.file_name().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        let Some((pkgver, _arch)) = file_name.strip_suffix(".xbps").and_then(|s| s.rsplit_once('.')) else {
+            continue;
+        };
+        let Some((name, version)) = split_pkgver(pkgver) else {
+            continue;
+        };
+
+        if installed.get(name).is_some_and(|v| v == version) {
+            continue;
+        }
+
+        let related: Vec<_> = [".sig", ".sig2"]
+            .iter()
+            .map(|ext| path.with_file_name(format!("{}{}", file_name, ext)))
+            .filter(|sig| sig.exists())
+            .collect();
+        let size = std::iter::once(&path)
+            .chain(&related)
+            .filter_map(|p| std::fs::metadata(p).ok())
+            .map(|m| m.len())
+            .sum();
+
+        cached.push(CachedPackage {
+            name: name.to_string(),
+            version: version.to_string(),
+            file: CacheFile {
+                path,
+                related,
+                size,
+                reason: "Cached package of a version no longer installed".to_string(),
+            },
+        });
+    }
+
+    Ok(backend::prune_cache(cached, scope.keep_cached))
+}
+
+pub fn read_pkgdb(root: &Path) -> anyhow::Result<Vec<XbpsPackage>> {
+    let file = std::fs::File::open(root.join(PKGDB_PATH))?;
+    parse_pkgdb(std::io::BufReader::new(file))
+}
+
+/// Every path recorded in the package's `.<name>-files.plist`.
+pub fn read_files_plist(root: &Path, name: &str) -> anyhow::Result<Vec<PathBuf>> {
+    let path = root.join(PKGDB_PATH).with_file_name(format!(".{}-files.plist", name));
+    let plist: FilesPlist = plist::from_file(path)?;
+
+    let files = [plist.files, plist.links, plist.conf_files, plist.dirs]
+        .into_iter()
+        .flatten()
+        .map(|entry| entry.file)
+        .collect();
+
+    Ok(files)
+}
+
+/// Parses the pkgdb plist, a dictionary of package name to package
+/// metadata, and fills in reverse dependencies.
+pub fn parse_pkgdb<R: std::io::Read + std::io::Seek>(reader: R) -> anyhow::Result<Vec<XbpsPackage>> {
+    let pkgdb: BTreeMap<String, PkgdbEntry> = plist::from_reader(reader)?;
+
+    let mut packages: Vec<XbpsPackage> = pkgdb
+        .into_iter()
+        // Keys like `_XBPS_ALTERNATIVES_` hold pkgdb metadata, not packages
+        .filter(|(name, _)| !name.starts_with('_'))
+        .map(|(name, entry)| XbpsPackage {
+            version: split_pkgver(&entry.pkgver)
+                .map(|(_, version)| version.to_string())
+                .unwrap_or_else(|| "unknown".to_string()),
+            name,
+            short_desc: entry.short_desc,
+            installed_size: entry.installed_size,
+            automatic_install: entry.automatic_install,
+            run_depends: entry.run_depends.iter().map(|d| dependency_name(d).to_string()).collect(),
+            provides: entry.provides.iter().map(|p| dependency_name(p).to_string()).collect(),
+            reverse_depends: Vec::new(),
+        })
+        .collect();
+
+    // A dependency on a virtual package counts for every package providing it
+    let mut providers: HashMap<&str, Vec<&str>> = HashMap::new();
+    for pkg in &packages {
+        providers.entry(&pkg.name).or_default().push(&pkg.name);
+        for virtual_name in &pkg.provides {
+            providers.entry(virtual_name).or_default().push(&pkg.name);
+        }
+    }
+
+    let mut reverse: HashMap<String, Vec<String>> = HashMap::new();
+    for pkg in &packages {
+        for dep in &pkg.run_depends {
+            for provider in providers.get(dep.as_str()).into_iter().flatten() {
+                let dependents = reverse.entry(provider.to_string()).or_default();
+                if !dependents.contains(&pkg.name) {
+                    dependents.push(pkg.name.clone());
+                }
+            }
+        }
+    }
+
+    for pkg in &mut packages {
+        pkg.reverse_depends = reverse.remove(&pkg.name).unwrap_or_default();
+    }
+
+    Ok(packages)
+}
+
+/// Packages an `xbps-remove -n` run would take out, from its
+/// `<pkgver> remove <arch> <repo> <size>` lines.
+fn parse_simulation(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut cols = line.split_whitespace();
+            let pkgver = cols.next()?;
+            (cols.next() == Some("remove")).then_some(pkgver)
+        })
+        .filter_map(|pkgver| split_pkgver(pkgver).map(|(name, _)| name.to_string()))
+        .collect()
+}
+
+/// Splits a pkgver such as `libfoo-bar-1.2.3_1` into `("libfoo-bar", "1.2.3_1")`.
+/// xbps versions never contain `-`, so the last one separates name and version.
+fn split_pkgver(pkgver: &str) -> Option<(&str, &str)> {
+    let (name, version) = pkgver.rsplit_once('-')?;
+    version.contains('_').then_some((name, version))
+}
+
+/// Reduces a dependency pattern (`glibc>=2.36_1`, `libfoo<2.0`,
+/// `bar-1.0_1`) or a provides entry (`java-runtime-17_1`) to the package name.
+fn dependency_name(pattern: &str) -> &str {
+    if let Some(end) = pattern.find(['<', '>', '=']) {
+        return &pattern[..end];
+    }
+
+    split_pkgver(pattern).map(|(name, _)| name).unwrap_or(pattern)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture_root() -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/xbps/root")
+    }
+
+    #[test]
+    fn splits_pkgver_with_dashed_name() {
+        assert_eq!(split_pkgver("xbps-triggers-0.128_1"), Some(("xbps-triggers", "0.128_1")));
+        assert_eq!(split_pkgver("xbps-triggers"), None);
+    }
+
+    #[test]
+    fn parses_xbps_remove_simulation() {
+        let output = "\
+mousepad-0.6.1_1 remove x86_64-musl https://repo-default.voidlinux.org/current/musl 2785KB
+libnotify-0.8.3_1 remove x86_64-musl https://repo-default.voidlinux.org/current/musl 131KB
+
+Size freed on disk:           2916KB
+";
+
+        assert_eq!(parse_simulation(output), ["mousepad", "libnotify"]);
+    }
+
+    #[test]
+    fn reduces_dependency_patterns() {
+        assert_eq!(dependency_name("glibc>=2.36_1"), "glibc");
+        assert_eq!(dependency_name("libfoo<2.0"), "libfoo");
+        assert_eq!(dependency_name("xbps-triggers-0.128_1"), "xbps-triggers");
+    }
+
+    #[test]
+    fn reads_package_metadata() {
+        let packages = read_pkgdb(&fixture_root()).unwrap();
+        let names: Vec<_> = packages.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["base-system", "glibc", "libnotify", "mousepad", "xbps-triggers"]);
+
+        let libnotify = packages.iter().find(|p| p.name == "libnotify").unwrap();
+        assert_eq!(libnotify.version, "0.8.3_1");
+        assert_eq!(libnotify.short_desc, "Desktop notification library");
+        assert_eq!(libnotify.installed_size, 102400);
+        assert!(libnotify.automatic_install);
+    }
+
+    #[test]
+    fn computes_reverse_dependencies() {
+        let packages = read_pkgdb(&fixture_root()).unwrap();
+        let glibc = packages.iter().find(|p| p.name == "glibc").unwrap();
+
+        assert_eq!(glibc.reverse_depends, ["base-system", "libnotify", "mousepad"]);
+    }
+
+    #[test]
+    fn reads_every_kind_of_file_from_files_plist() {
+        let files = read_files_plist(&fixture_root(), "mousepad").unwrap();
+
+        assert_eq!(
+            files,
+            [
+                PathBuf::from("/usr/bin/mousepad"),
+                PathBuf::from("/usr/share/applications/org.xfce.mousepad.desktop"),
+                PathBuf::from("/usr/share/mousepad/plugins"),
+                PathBuf::from("/usr/share/mousepad"),
+            ]
+        );
+    }
+
+    #[test]
+    fn base_system_closure_is_essential() {
+        let scope = ScanScope {
+            orphans: true,
+            residual: true,
+            root: fixture_root(),
+            keep_kernels: 2,
+            keep_cached: 1,
+            protected: Vec::new(),
+        };
+        let mut essential: Vec<_> = Xbps.essential_packages(&scope).unwrap().into_iter().collect();
+        essential.sort();
+
+        assert_eq!(essential, ["base-system", "glibc"]);
+    }
+
+    #[test]
+    fn orphans_are_automatic_and_unused() {
+        let orphans = list_orphans(&fixture_root()).unwrap();
+        let names: Vec<_> = orphans.iter().map(|p| p.name.as_str()).collect();
+
+        assert_eq!(names, ["libnotify"]);
+        assert_eq!(orphans[0].description, "Desktop notification library");
     }
 }
@@ -0,0 +1,105 @@
+use crate::backend::{PackageBackend, ScanScope};
+use crate::rpm;
+use crate::types::{Package, PackageSystem, OS};
+use std::path::Path;
+use std::process::Command;
+
+pub struct Zypper;
+
+impl PackageBackend for Zypper {
+    fn name(&self) -> &'static str {
+        "zypper"
+    }
+
+    fn os(&self) -> OS {
+        OS::OpenSuse
+    }
+
+    fn system(&self) -> PackageSystem {
+        PackageSystem::Zypper
+    }
+
+    fn detect(&self, root: &Path) -> bool {
+        root.join("usr/bin/zypper").exists()
+    }
+
+    fn supports_root(&self) -> bool {
+        false
+    }
+
+    fn list_candidates(&self, scope: &ScanScope) -> anyhow::Result<Vec<Package>> {
+        if !scope.orphans {
+            return Ok(Vec::new());
+        }
+
+        let mut packages = list_packages("--orphaned", "Orphaned: no repository provides it")?;
+        for pkg in list_packages("--unneeded", "Unneeded dependency")? {
+            if !packages.iter().any(|p| p.name == pkg.name) {
+                packages.push(pkg);
+            }
+        }
+
+        Ok(packages)
+    }
+
+    fn installed_packages(&self, _scope: &ScanScope) -> anyhow::Result<Vec<String>> {
+        rpm::list_installed()
+    }
+
+    fn removal_command(&self, pkgs: &[&Package]) -> Vec<String> {
+        let mut cmd = vec![
+            "zypper".to_string(),
+            "--non-interactive".to_string(),
+            "rm".to_string(),
+            "--clean-deps".to_string(),
+        ];
+        cmd.extend(pkgs.iter().map(|p| p.name.clone()));
+        cmd
+    }
+}
+
+/// Runs `zypper packages <filter>` and keeps the installed rows.
+fn list_packages(filter: &str, description: &str) -> anyhow::Result<Vec<Package>> {
+    let output = Command::new("zypper")
+        .args(["--quiet", "--no-refresh", "packages", filter])
+        .output()?;
+
+    if !output.status.success() {
+        anyhow::bail!("zypper packages {} failed", filter);
+    }
+
+    let stdout = String::from_utf8(output.stdout)?;
+    Ok(parse_package_table(&stdout, description))
+}
+
+/// Parses zypper's `S | Repository | Name | Version | Arch` table.
+fn parse_package_table(table: &str, description: &str) -> Vec<Package> {
+    let mut lines = table.lines().filter(|l| l.contains('|'));
+    let Some(header) = lines.next() else {
+        return Vec::new();
+    };
+
+    let columns: Vec<&str> = header.split('|').map(str::trim).collect();
+    let column = |name: &str| columns.iter().position(|c| *c == name);
+    let (Some(status_col), Some(name_col), Some(version_col)) = (column("S"), column("Name"), column("Version")) else {
+        return Vec::new();
+    };
+
+    lines
+        .map(|line| line.split('|').map(str::trim).collect::<Vec<_>>())
+        .filter(|cells| cells.get(status_col).is_some_and(|s| s.starts_with('i')))
+        .filter_map(|cells| {
+            Some(Package {
+                name: cells.get(name_col)?.to_string(),
+                version: cells.get(version_col)?.to_string(),
+                description: description.to_string(),
+                installed: true,
+                installed_size: None,
+                system: PackageSystem::Zypper,
+                explanation: None,
+                protected: false,
+                category: None,
+            })
+        })
+        .collect()
+}